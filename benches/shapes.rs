@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use cbr_alarm::{anime::AnimChrono, shapes::ShapeSelect};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Color,
+    widgets::{canvas::Canvas, Widget},
+};
+
+const SIZES: [(u16, u16); 3] = [(80, 24), (120, 40), (200, 60)];
+
+// a frame of the animation, the traced path is cached after the first iteration
+fn bench_cached(c: &mut Criterion) {
+    let mut group = c.benchmark_group("anim_chrono");
+    for select in 0..ShapeSelect::COUNT {
+        let shape = ShapeSelect::select_from(select, Color::LightRed);
+        let name = ShapeSelect::name_of(select);
+        let mut anim = AnimChrono::new(shape, Duration::from_secs(60));
+        anim.update(Duration::from_secs(20), || {});
+        for (w, h) in SIZES {
+            let area = Rect::new(0, 0, w, h);
+            let mut buf = Buffer::empty(area);
+            group.bench_with_input(
+                BenchmarkId::new(name, format!("{w}x{h}")),
+                &area,
+                |b, area| {
+                    b.iter(|| (&anim).render(*area, &mut buf));
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+// frames of a moving countdown, each one only repaints where the progress moved
+fn bench_moving(c: &mut Criterion) {
+    let mut group = c.benchmark_group("anim_chrono_moving");
+    for select in 0..ShapeSelect::COUNT {
+        let shape = ShapeSelect::select_from(select, Color::LightRed);
+        if shape.look().is_none() {
+            continue;
+        }
+        let name = ShapeSelect::name_of(select);
+        let mut anim = AnimChrono::new(shape, Duration::from_secs(60));
+        for (w, h) in SIZES {
+            let area = Rect::new(0, 0, w, h);
+            let mut buf = Buffer::empty(area);
+            let mut progress = 0.0;
+            group.bench_with_input(
+                BenchmarkId::new(name, format!("{w}x{h}")),
+                &area,
+                |b, area| {
+                    b.iter(|| {
+                        progress = (progress + 0.001) % 1.0;
+                        anim.set_progress(progress);
+                        (&anim).render(*area, &mut buf);
+                    });
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+// the shape traced and drawn from scratch, as every frame did before caching
+fn bench_uncached(c: &mut Criterion) {
+    let mut group = c.benchmark_group("shape_draw");
+    for select in 0..ShapeSelect::COUNT {
+        let shape = ShapeSelect::select_from(select, Color::LightRed);
+        if shape.cell_renderer().is_some() {
+            continue;
+        }
+        let name = ShapeSelect::name_of(select);
+        for (w, h) in SIZES {
+            let area = Rect::new(0, 0, w, h);
+            let (right, top) = (f64::from(w), f64::from(h).mul_add(2.0, -4.0));
+            let centered = shape.center(right, top, 0.33).with_bgcolor(Color::DarkGray);
+            let mut buf = Buffer::empty(area);
+            group.bench_with_input(
+                BenchmarkId::new(name, format!("{w}x{h}")),
+                &area,
+                |b, area| {
+                    b.iter(|| {
+                        Canvas::default()
+                            .marker(shape.get_marker())
+                            .x_bounds([0.0, right])
+                            .y_bounds([0.0, top])
+                            .paint(|ctx| ctx.draw(&centered))
+                            .render(*area, &mut buf)
+                    });
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_cached, bench_moving, bench_uncached);
+criterion_main!(benches);
@@ -1,20 +1,271 @@
-use crate::shapes;
-use crate::shapes::ShapeSelect;
+use crate::clock::{self, SharedClock};
+use crate::marker::{self, MarkerKind, MarkerPolicy};
+use crate::shapes::{marker_resolution, Gradient, ShapeSelect, Traced, TracedLook};
+use crate::theme;
 
 use ratatui::{
-    layout::Rect,
+    layout::{Margin, Position, Rect},
     prelude::Buffer,
-    style::Color,
-    widgets::{canvas::Canvas, Block, Widget},
+    style::{Color, Modifier, Style},
+    symbols::{self, border},
+    widgets::{
+        canvas::{Canvas, Context, Painter, Shape},
+        Block, Widget,
+    },
 };
-use std::time::Duration;
+use std::{
+    cell::{Cell, RefCell},
+    time::{Duration, Instant},
+};
+
+/// Effects layered on the animation during the last seconds of the countdown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Urgency {
+    pub threshold: Duration,
+    pub pulse: bool,
+    pub blink_border: bool,
+    pub shake: bool,
+    pub flash: bool,
+}
+
+impl Default for Urgency {
+    fn default() -> Self {
+        Self {
+            threshold: Duration::from_secs(10),
+            pulse: true,
+            blink_border: true,
+            shake: true,
+            flash: true,
+        }
+    }
+}
+
+/// Curve used when the displayed progress catches up after a timeout change.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Easing {
+    Linear,
+    #[default]
+    EaseOutCubic,
+    EaseInOutQuad,
+    EaseOutElastic,
+}
+
+impl Easing {
+    /// Maps `t` in `0.0..=1.0` to the eased position, 0 and 1 are fixed points.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+            Self::EaseInOutQuad => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Self::EaseOutElastic => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    let c4 = std::f64::consts::TAU / 3.0;
+                    2f64.powf(-10.0 * t) * ((t * 10.0 - 0.75) * c4).sin() + 1.0
+                }
+            }
+        }
+    }
+}
+
+/// What the timeout keys change.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Adjust {
+    /// the time left, the total grows when the time left goes past it
+    Remaining,
+    /// the total, the time left is kept
+    Total,
+    /// both, the elapsed time is kept
+    #[default]
+    Both,
+}
+
+/// Time added or removed by the timeout keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Steps {
+    pub small: Duration,
+    pub medium: Duration,
+    pub large: Duration,
+    /// countdown run by the snooze key once complete
+    pub snooze: Duration,
+}
+
+impl Default for Steps {
+    fn default() -> Self {
+        Self {
+            small: Duration::from_secs(1),
+            medium: Duration::from_secs(60),
+            large: Duration::from_secs(600),
+            snooze: Duration::from_secs(300),
+        }
+    }
+}
+
+// displayed progress easing from `from` toward the real progress
+#[derive(Debug, Clone, Copy)]
+struct Tween {
+    from: f64,
+    elapsed: Duration,
+}
+
+// traced path of the shape for one canvas size and marker, see [`Traced`]
+#[derive(Debug, Default)]
+struct PathCache {
+    key: Option<(u16, u16, symbols::Marker)>,
+    cells: Vec<(usize, usize)>,
+    painted: Option<Painted>,
+}
+
+// the canvas last painted over the path, kept while the colours don't change so
+// that a new progress only repaints the cells between the old and the new one
+#[derive(Debug)]
+struct Painted {
+    color: Color,
+    bgcolor: Option<Color>,
+    along: Option<Gradient>,
+    filled: usize,
+    buf: Buffer,        // the canvas cells, from the origin
+    ink: Vec<Position>, // the cells of `buf` with a symbol
+}
+
+impl Painted {
+    fn new(look: &TracedLook, filled: usize, buf: Buffer) -> Self {
+        let mut painted = Self {
+            color: look.color,
+            bgcolor: look.bgcolor,
+            along: look.along.cloned(),
+            filled,
+            buf,
+            ink: vec![],
+        };
+        painted.find_ink();
+        painted
+    }
+
+    fn find_ink(&mut self) {
+        let buf = &self.buf;
+        self.ink = buf
+            .area
+            .positions()
+            .filter(|&p| buf[p].symbol() != " ")
+            .collect();
+    }
+
+    // replaces the cells under `patch`, painted on a canvas of its size
+    fn patch(&mut self, patch: &Buffer, filled: usize) {
+        for pos in patch.area.positions() {
+            self.buf[pos] = patch[pos].clone();
+        }
+        self.filled = filled;
+        self.find_ink();
+    }
+
+    // copies the canvas over `area` the way the canvas widget draws: the background
+    // is reset and only the cells with a symbol are written
+    fn draw(&self, area: Rect, buf: &mut Buffer) {
+        buf.set_style(area, Style::default().bg(Color::Reset));
+        for &pos in &self.ink {
+            let cell = &self.buf[pos];
+            let target = &mut buf[(area.x + pos.x, area.y + pos.y)];
+            target.set_symbol(cell.symbol());
+            if cell.fg != Color::Reset {
+                target.set_fg(cell.fg);
+            }
+            if cell.bg != Color::Reset {
+                target.set_bg(cell.bg);
+            }
+        }
+    }
+
+    fn same_look(&self, look: &TracedLook) -> bool {
+        // the colour of the progress is not used along a gradient
+        let color = look.along.is_some() || self.color == look.color;
+        color && self.bgcolor == look.bgcolor && self.along.as_ref() == look.along
+    }
+}
+
+// draws a shape over the cells of the cache
+struct CachedShape<'a> {
+    shape: &'a ShapeSelect,
+    cells: &'a [(usize, usize)],
+}
+
+impl Shape for CachedShape<'_> {
+    fn draw(&self, painter: &mut Painter<'_, '_>) {
+        self.shape.draw_traced(painter, self.cells);
+    }
+}
+
+// the cells of the cache over the terminal cells of `area`, drawn on a canvas of
+// the size of `area`
+struct CachedPatch<'a> {
+    look: TracedLook<'a>,
+    cells: &'a [(usize, usize)],
+    area: Rect,
+    resolution: (usize, usize), // grid cells per terminal cell
+}
+
+impl Shape for CachedPatch<'_> {
+    fn draw(&self, painter: &mut Painter<'_, '_>) {
+        let (rx, ry) = self.resolution;
+        let origin = (usize::from(self.area.x) * rx, usize::from(self.area.y) * ry);
+        let inside = |(x, y): (usize, usize)| {
+            let (cx, cy) = (x / rx, y / ry);
+            self.area.contains(Position::new(cx as u16, cy as u16))
+        };
+        self.look.paint_where(painter, self.cells, origin, inside);
+    }
+}
+
+const TWEEN_DURATION: Duration = Duration::from_millis(300);
+const BLINK_PERIOD: Duration = Duration::from_millis(500);
+const FLASH_DURATION: Duration = Duration::from_millis(600);
+// progress per second above which a few frames a second look jerky
+const SMOOTH_RATE: f64 = 0.005;
 
 #[derive(Debug)]
 pub struct AnimChrono {
     shapes_selected: ShapeSelect,
     pub timeout: Duration, // todo: use u64 msecs
     pub remaining: Duration,
+    original: Duration,         // timeout before any edit, restored by a reset
+    last_run: Option<Duration>, // timeout of the last countdown that completed
+    snoozes: u32,               // since the countdown completed
+    paused: bool,
+    adjust: Adjust,
     complete: bool,
+    urgency: Option<Urgency>,
+    clock: Duration, // time spent animating, drives the effect phases
+    source: SharedClock,
+    ticked: Duration, // reading of `source` at the last tick
+    completed_at: Option<Duration>,
+    tween: Option<Tween>,
+    easing: Easing,
+    markers: MarkerPolicy,
+    bgcolor: Color, // elapsed part of the shape
+    cache: RefCell<PathCache>,
+    last_area: Cell<Rect>, // inside the border, where the shape was drawn last
+    draw_time: Cell<Duration>, // spent drawing the shape in the last render
+    pub label: String,
 }
 
 impl AnimChrono {
@@ -23,65 +274,962 @@ impl AnimChrono {
             shapes_selected,
             timeout,
             remaining: timeout,
+            original: timeout,
+            last_run: None,
+            snoozes: 0,
+            paused: false,
+            adjust: Adjust::default(),
             complete: false,
+            urgency: Some(Urgency::default()),
+            clock: Duration::ZERO,
+            source: clock::system(),
+            ticked: Duration::ZERO,
+            completed_at: None,
+            tween: None,
+            easing: Easing::default(),
+            markers: MarkerPolicy::default(),
+            bgcolor: Color::DarkGray,
+            cache: RefCell::default(),
+            last_area: Cell::default(),
+            draw_time: Cell::default(),
+            label: String::new(),
+        }
+    }
+
+    /// Replaces the animated shape.
+    pub fn with_shape(mut self, shape: ShapeSelect) -> Self {
+        self.set_shape(shape);
+        self
+    }
+
+    pub fn set_shape(&mut self, shape: ShapeSelect) {
+        self.shapes_selected = shape.with_label(&self.label);
+        self.cache = RefCell::default();
+    }
+
+    /// Starts over with a new timeout.
+    pub fn start(&mut self, timeout: Duration) {
+        self.original = timeout;
+        self.restart();
+    }
+
+    // runs `timeout` from the start, paused or not
+    fn rewind(&mut self, timeout: Duration) {
+        self.start_tween();
+        self.timeout = timeout;
+        self.remaining = timeout;
+        self.complete = false;
+        self.completed_at = None;
+        self.snoozes = 0;
+    }
+
+    /// Back to the original timeout, still paused if it was.
+    pub fn reset(&mut self) {
+        self.rewind(self.original);
+    }
+
+    /// Back to the original timeout and running.
+    pub fn restart(&mut self) {
+        self.reset();
+        self.paused = false;
+    }
+
+    /// Runs again the timeout of the last completed countdown, edits included.
+    pub fn repeat_last(&mut self) {
+        self.rewind(self.last_run.unwrap_or(self.original));
+        self.paused = false;
+    }
+
+    /// Once complete, counts down `by` again. A reset still goes back to the
+    /// original timeout, a repeat to the countdown that was snoozed.
+    pub fn snooze(&mut self, by: Duration) {
+        if !self.complete {
+            return;
         }
+        let snoozes = self.snoozes + 1;
+        self.rewind(by);
+        self.snoozes = snoozes;
+        self.paused = false;
+    }
+
+    /// Snoozes since the countdown completed, 0 once it starts over.
+    pub fn snoozes(&self) -> u32 {
+        self.snoozes
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Timeout the countdown started with, before any edit.
+    pub fn original(&self) -> Duration {
+        self.original
+    }
+
+    /// Name of the timer, also seeds the shapes that depend on it.
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = label.into();
+        self.shapes_selected = self.shapes_selected.with_label(&self.label);
+        self
+    }
+
+    pub fn with_colors(mut self, fg: Color, bg: Color) -> Self {
+        self.set_colors(fg, bg);
+        self
+    }
+
+    pub fn set_colors(&mut self, fg: Color, bg: Color) {
+        self.shapes_selected.set_color(fg);
+        self.bgcolor = bg;
+    }
+
+    /// Clock read by [`Self::tick`].
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.ticked = clock.now();
+        self.source = clock;
+        self
+    }
+
+    pub fn with_markers(mut self, markers: MarkerPolicy) -> Self {
+        self.markers = markers;
+        self
+    }
+
+    pub fn with_adjust(mut self, adjust: Adjust) -> Self {
+        self.adjust = adjust;
+        self
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// `None` disables the urgency effects, e.g. for reduced motion.
+    pub fn with_urgency(mut self, urgency: Option<Urgency>) -> Self {
+        self.urgency = urgency;
+        self
+    }
+
+    pub fn with_gradient(mut self, gradient: Gradient, along_path: bool) -> Self {
+        self.shapes_selected = self.shapes_selected.with_gradient(gradient, along_path);
+        self
+    }
+
+    /// Moves the countdown by the time read on the clock since the last tick.
+    pub fn tick<F>(&mut self, cb_complete: F)
+    where
+        F: Fn(),
+    {
+        let now = self.source.now();
+        let elapsed = now.saturating_sub(self.ticked);
+        self.ticked = now;
+        self.update(elapsed, cb_complete);
     }
 
     pub fn update<F>(&mut self, elapsed: Duration, cb_complete: F)
     where
         F: Fn(),
     {
+        self.clock = self.clock.saturating_add(elapsed);
+        if let Some(t) = &mut self.tween {
+            t.elapsed = t.elapsed.saturating_add(elapsed);
+            if t.elapsed >= TWEEN_DURATION {
+                self.tween = None;
+            }
+        }
+        if self.paused {
+            return;
+        }
         self.remaining = self.remaining.saturating_sub(elapsed);
+        let perc = self.displayed_progress();
+        self.shapes_selected
+            .tick(elapsed, perc, self.last_area.get());
         if !self.complete && self.remaining.as_secs() == 0 {
             self.complete = true;
+            self.completed_at = Some(self.clock);
+            if self.snoozes == 0 {
+                self.last_run = Some(self.timeout);
+            }
             cb_complete();
         }
     }
 
-    pub fn increase_timeout(&mut self, tm: u64) {
-        self.timeout = self.timeout.saturating_add(Duration::new(tm, 0));
-        self.remaining = self.remaining.saturating_add(Duration::new(tm, 0));
+    fn urgent(&self) -> Option<&Urgency> {
+        self.urgency
+            .as_ref()
+            .filter(|u| !self.complete && !self.paused && self.remaining <= u.threshold)
+    }
+
+    fn blink_on(&self) -> bool {
+        (self.clock.as_millis() / BLINK_PERIOD.as_millis()).is_multiple_of(2)
+    }
+
+    /// Style of the border around the animation, blinks when urgent.
+    pub fn border_style(&self) -> Style {
+        match self.urgent() {
+            Some(u) if u.blink_border && self.blink_on() => {
+                Style::new().fg(theme::RED).add_modifier(Modifier::BOLD)
+            }
+            _ => Style::new(),
+        }
+    }
+
+    /// True for a short moment once the countdown reached zero.
+    pub fn flashing(&self) -> bool {
+        let Some(at) = self.completed_at else {
+            return false;
+        };
+        let since = self.clock.saturating_sub(at);
+        self.urgency.as_ref().is_some_and(|u| u.flash) && since < FLASH_DURATION && self.blink_on()
+    }
+
+    pub fn shape_name(&self) -> &str {
+        self.shapes_selected.name()
+    }
+
+    /// Time spent drawing the shape during the last render.
+    pub fn draw_time(&self) -> Duration {
+        self.draw_time.get()
+    }
+
+    /// Something on screen needs a full frame rate to look smooth.
+    pub fn animating(&self) -> bool {
+        let flash = self
+            .completed_at
+            .is_some_and(|at| self.clock.saturating_sub(at) < FLASH_DURATION);
+        if self.tween.is_some() || self.urgent().is_some() || flash {
+            return true;
+        }
+        !self.complete
+            && !self.paused
+            && (self.shapes_selected.animated() || self.timeout.as_secs_f64() * SMOOTH_RATE < 1.0)
+    }
+
+    // brightness factor in 0.5..=1.0, two beats a second
+    fn pulse_factor(&self) -> Option<f64> {
+        self.urgent().filter(|u| u.pulse).map(|_| {
+            let phase = self.clock.as_secs_f64() * 2.0 * std::f64::consts::TAU;
+            0.75 + 0.25 * phase.sin()
+        })
+    }
+
+    // horizontal offset in cells, cycles through a few positions
+    fn shake_offset(&self) -> i8 {
+        const OFFSETS: [i8; 4] = [1, 0, -1, 0];
+        match self.urgent() {
+            Some(u) if u.shake => OFFSETS[(self.clock.as_millis() / 50) as usize % OFFSETS.len()],
+            _ => 0,
+        }
+    }
+
+    /// Real progress in `0.0..=1.0`.
+    pub fn progress(&self) -> f64 {
+        if self.timeout.is_zero() {
+            return 1.0;
+        }
+        1.0 - self.remaining.as_secs_f64() / self.timeout.as_secs_f64()
+    }
+
+    /// Progress shown by the animation, eases toward [`Self::progress`] after a change.
+    pub fn displayed_progress(&self) -> f64 {
+        let target = self.progress();
+        match self.tween {
+            Some(t) => {
+                let k = t.elapsed.as_secs_f64() / TWEEN_DURATION.as_secs_f64();
+                t.from + (target - t.from) * self.easing.apply(k)
+            }
+            None => target,
+        }
+    }
+
+    fn start_tween(&mut self) {
+        self.tween = Some(Tween {
+            from: self.displayed_progress(),
+            elapsed: Duration::ZERO,
+        });
+    }
+
+    /// Adds `by` to what [`Adjust`] selects, a completed countdown runs again
+    /// when time is left.
+    pub fn increase_timeout(&mut self, by: Duration) {
+        self.start_tween();
+        match self.adjust {
+            Adjust::Remaining => {
+                self.remaining = self.remaining.saturating_add(by);
+                self.timeout = self.timeout.max(self.remaining);
+            }
+            Adjust::Total => self.timeout = self.timeout.saturating_add(by),
+            Adjust::Both => {
+                self.timeout = self.timeout.saturating_add(by);
+                self.remaining = self.remaining.saturating_add(by);
+            }
+        }
+        self.reopen();
+    }
+
+    // a completed countdown runs again when time is left, same test as in `update`
+    fn reopen(&mut self) {
+        if self.complete && self.remaining.as_secs() > 0 {
+            self.complete = false;
+            self.completed_at = None;
+        }
+    }
+
+    /// Sets the time left to match `progress`, the way a kitchen timer dial is turned.
+    pub fn set_progress(&mut self, progress: f64) {
+        let progress = progress.clamp(0.0, 1.0);
+        // follows the pointer, no easing
+        self.tween = None;
+        self.remaining = self.timeout.mul_f64(1.0 - progress);
+        self.reopen();
     }
 
-    pub fn decrease_timeout(&mut self, tm: u64) {
-        self.timeout = self.timeout.saturating_sub(Duration::new(tm, 0));
-        self.remaining = self.timeout;
-        self.remaining = self.remaining.saturating_sub(Duration::new(tm, 0));
+    /// Where the shape was drawn last, inside the border.
+    pub fn area(&self) -> Rect {
+        self.last_area.get()
+    }
+
+    /// Progress pointed at on the arc dial from the cell `pos`, `None` for the
+    /// other shapes, outside the dial or at its center.
+    pub fn dial_progress(&self, pos: Position) -> Option<f64> {
+        if !matches!(self.shapes_selected, ShapeSelect::ArcSelect(_)) {
+            return None;
+        }
+        let area = self.last_area.get();
+        if area.is_empty() || !area.contains(pos) {
+            return None;
+        }
+        // canvas units, with the bounds of `render_canvas` for the bordered area
+        let right = f64::from(area.width + 2);
+        let top = f64::from(area.height + 2).mul_add(2.0, -4.0);
+        let x = (f64::from(pos.x - area.x) + 0.5) / f64::from(area.width) * right;
+        let y = top - (f64::from(pos.y - area.y) + 0.5) / f64::from(area.height) * top;
+        let (dx, dy) = (x - right / 2.0, y - top / 2.0);
+        if dx.hypot(dy) < 1.0 {
+            return None;
+        }
+        // the arc starts on the right and goes counterclockwise
+        let tau = std::f64::consts::TAU;
+        Some(dy.atan2(dx).rem_euclid(tau) / tau)
+    }
+
+    /// Removes `by` from what [`Adjust`] selects, the total never gets below
+    /// the time left.
+    pub fn decrease_timeout(&mut self, by: Duration) {
+        self.start_tween();
+        match self.adjust {
+            Adjust::Remaining => self.remaining = self.remaining.saturating_sub(by),
+            Adjust::Total => self.timeout = self.timeout.saturating_sub(by).max(self.remaining),
+            Adjust::Both => {
+                self.timeout = self.timeout.saturating_sub(by);
+                self.remaining = self.remaining.saturating_sub(by);
+            }
+        }
     }
 }
 
-impl Widget for &AnimChrono {
-    fn render(self, area: Rect, buf: &mut Buffer) {
+impl AnimChrono {
+    fn render_canvas(&self, shape: &ShapeSelect, kind: MarkerKind, area: Rect, buf: &mut Buffer) {
         let left = 0.0;
         let right = f64::from(area.width);
         let bottom = 0.0;
-        let complete_perc = self.remaining.as_millis() as f64 / self.timeout.as_millis() as f64;
-        let complete_perc = 1.0 - complete_perc;
         let top = f64::from(area.height).mul_add(2.0, -4.0);
+        let marker = kind.marker();
+        let bounds = ([left, right], [bottom, top]);
 
-        //TODO: can avoid the clone if we don't stop the shapes_selected in App
-        // instead we create it in this function
-        let shape = match &self.shapes_selected {
-            shapes::ShapeSelect::ArcSelect(a) => {
-                ShapeSelect::ArcSelect(a.clone().center(right, top, complete_perc))
+        // the path only changes with the canvas size and marker, it is traced again then
+        let inner = area.inner(Margin::new(1, 1));
+        let mut cache = self.cache.borrow_mut();
+        let cache = &mut *cache;
+        let key = Some((inner.width, inner.height, marker));
+        if cache.key != key {
+            let mut ctx = Context::new(
+                inner.width,
+                inner.height,
+                [left, right],
+                [bottom, top],
+                marker,
+            );
+            cache.cells = shape.trace(&mut Painter::from(&mut ctx));
+            cache.key = key;
+            cache.painted = None;
+        }
+        let cells = &cache.cells;
+        border_block(kind).render(area, buf);
+        let Some(look) = shape.look() else {
+            // sparks and the like move on their own, everything is painted again
+            paint_canvas(&CachedShape { shape, cells }, marker, bounds, inner, buf);
+            return;
+        };
+        let filled = look.filled(cells.len());
+        match &mut cache.painted {
+            Some(painted) if painted.same_look(&look) => {
+                if painted.filled != filled {
+                    let moved = &cells[painted.filled.min(filled)..painted.filled.max(filled)];
+                    let resolution = marker_resolution(marker);
+                    let patch = CachedPatch {
+                        look,
+                        cells,
+                        area: terminal_cells(moved, resolution),
+                        resolution,
+                    };
+                    let mut patched = Buffer::empty(patch.area);
+                    paint_canvas(&patch, marker, bounds, patch.area, &mut patched);
+                    painted.patch(&patched, filled);
+                }
             }
-            shapes::ShapeSelect::SpiralSelect(s) => {
-                ShapeSelect::SpiralSelect(s.clone().center(right, top, complete_perc))
+            painted => {
+                let mut full = Buffer::empty(Rect::new(0, 0, inner.width, inner.height));
+                let cached = CachedShape { shape, cells };
+                paint_canvas(&cached, marker, bounds, full.area, &mut full);
+                *painted = Some(Painted::new(&look, filled, full));
             }
-            shapes::ShapeSelect::ZigZagSelect(z) => {
-                ShapeSelect::ZigZagSelect(z.clone().center(right, top, complete_perc))
+        }
+        if let Some(painted) = &cache.painted {
+            painted.draw(inner, buf);
+        }
+    }
+}
+
+fn paint_canvas<S: Shape>(
+    shape: &S,
+    marker: symbols::Marker,
+    (x_bounds, y_bounds): ([f64; 2], [f64; 2]),
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    Canvas::default()
+        .marker(marker)
+        .x_bounds(x_bounds)
+        .y_bounds(y_bounds)
+        .paint(|ctx| ctx.draw(shape))
+        .render(area, buf);
+}
+
+// the terminal cells covering the grid `cells`
+fn terminal_cells(cells: &[(usize, usize)], (rx, ry): (usize, usize)) -> Rect {
+    let (x0, y0, x1, y1) = cells.iter().fold(
+        (usize::MAX, usize::MAX, 0, 0),
+        |(x0, y0, x1, y1), &(x, y)| {
+            (
+                x0.min(x / rx),
+                y0.min(y / ry),
+                x1.max(x / rx),
+                y1.max(y / ry),
+            )
+        },
+    );
+    Rect::new(
+        x0 as u16,
+        y0 as u16,
+        (x1 - x0 + 1) as u16,
+        (y1 - y0 + 1) as u16,
+    )
+}
+
+impl Widget for &AnimChrono {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let right = f64::from(area.width);
+        let top = f64::from(area.height).mul_add(2.0, -4.0);
+        let complete_perc = self.displayed_progress();
+
+        let bg = self.bgcolor;
+        let shape = self
+            .shapes_selected
+            .center(right, top, complete_perc)
+            .with_bgcolor(bg);
+        let kind = self.markers.pick(shape.get_marker());
+        let shape = shape.with_marker(kind.marker());
+        let inner = area.inner(Margin::new(1, 1));
+        self.last_area.set(inner);
+        let start = Instant::now();
+        match shape.cell_renderer() {
+            Some(cells) => {
+                border_block(kind).render(area, buf);
+                cells.render_cells(inner, buf);
+            }
+            None => self.render_canvas(&shape, kind, area, buf),
+        }
+        self.draw_time.set(start.elapsed());
+        if kind == MarkerKind::Ascii {
+            marker::asciify(buf, inner);
+        }
+        shift_columns(buf, inner, self.shake_offset());
+
+        if let Some(factor) = self.pulse_factor() {
+            for pos in area.positions() {
+                if let Some(cell) = buf.cell_mut(pos) {
+                    if cell.fg != bg {
+                        cell.fg = scale_brightness(cell.fg, factor);
+                    }
+                }
             }
+        }
+    }
+}
+
+// plain borders, or ascii ones when the terminal can't draw box characters
+fn border_block(kind: MarkerKind) -> Block<'static> {
+    if kind == MarkerKind::Ascii {
+        Block::bordered().border_set(marker::ASCII_BORDER)
+    } else {
+        Block::bordered().border_set(border::PLAIN)
+    }
+}
+
+// move the content of `area` by `dx` columns, the vacated edge keeps its old cells
+fn shift_columns(buf: &mut Buffer, area: Rect, dx: i8) {
+    if dx == 0 || area.width < 2 {
+        return;
+    }
+    for y in area.top()..area.bottom() {
+        let xs: Vec<u16> = if dx > 0 {
+            (area.left() + 1..area.right()).rev().collect()
+        } else {
+            (area.left()..area.right() - 1).collect()
+        };
+        for x in xs {
+            let from = if dx > 0 { x - 1 } else { x + 1 };
+            buf[(x, y)] = buf[(from, y)].clone();
+        }
+    }
+}
+
+fn scale_brightness(c: Color, factor: f64) -> Color {
+    match theme::to_rgb(c) {
+        Some((r, g, b)) => {
+            let scale = |v: u8| (f64::from(v) * factor).round() as u8;
+            Color::Rgb(scale(r), scale(g), scale(b))
+        }
+        None => c,
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use clap::ValueEnum;
+    use ratatui::style::Color;
+
+    fn chrono(secs: u64) -> AnimChrono {
+        AnimChrono::new(
+            ShapeSelect::select_from(0, Color::Red),
+            Duration::from_secs(secs),
+        )
+    }
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    // (timeout, remaining) after `f`, 30s into a 60s countdown
+    fn adjusted(adjust: Adjust, f: impl Fn(&mut AnimChrono)) -> (u64, u64) {
+        let mut c = chrono(60).with_adjust(adjust);
+        c.update(secs(30), || {});
+        f(&mut c);
+        (c.timeout.as_secs(), c.remaining.as_secs())
+    }
+
+    #[test]
+    fn when_easing_should_keep_end_points() {
+        for e in [
+            Easing::Linear,
+            Easing::EaseOutCubic,
+            Easing::EaseInOutQuad,
+            Easing::EaseOutElastic,
+        ] {
+            assert_eq!(0.0, e.apply(0.0), "{e:?}");
+            assert_eq!(1.0, e.apply(1.0), "{e:?}");
+        }
+    }
+
+    #[test]
+    fn when_timeout_changes_should_ease_displayed_progress() {
+        let mut a = chrono(100);
+        a.update(Duration::from_secs(50), || {});
+        a.increase_timeout(secs(100));
+        assert_eq!(0.5, a.displayed_progress());
+        a.update(Duration::from_millis(150), || {});
+        let shown = a.displayed_progress();
+        assert!(shown < 0.5 && shown > a.progress());
+        a.update(TWEEN_DURATION, || {});
+        assert_eq!(a.progress(), a.displayed_progress());
+    }
+
+    #[test]
+    fn when_tweening_should_not_change_remaining_time() {
+        let mut a = chrono(100);
+        a.increase_timeout(secs(60));
+        a.update(Duration::from_millis(100), || {});
+        assert_eq!(Duration::from_millis(159_900), a.remaining);
+    }
+
+    #[test]
+    fn when_long_timer_should_only_animate_when_something_moves() {
+        let noop = || {};
+        let mut c = chrono(3600);
+        assert!(!c.animating());
+        c.increase_timeout(secs(60));
+        assert!(c.animating());
+        c.update(TWEEN_DURATION, noop);
+        assert!(!c.animating());
+        c.update(Duration::from_secs(3660 - 5), noop);
+        assert!(c.animating()); // urgent
+        c.update(Duration::from_secs(5), noop);
+        assert!(c.animating()); // flash
+        c.update(FLASH_DURATION, noop);
+        assert!(!c.animating());
+        assert!(chrono(60).animating());
+    }
+
+    #[test]
+    fn when_under_threshold_should_turn_on_urgency_effects() {
+        let mut c = chrono(60);
+        c.update(secs(49), || {});
+        assert_eq!(Style::new(), c.border_style());
+        assert_eq!(None, c.pulse_factor());
+        assert_eq!(0, c.shake_offset());
+
+        c.update(secs(1), || {});
+        assert_eq!(
+            Style::new().fg(theme::RED).add_modifier(Modifier::BOLD),
+            c.border_style()
+        );
+        let factor = c.pulse_factor().unwrap();
+        assert!((0.5..=1.0).contains(&factor), "{factor}");
+        assert_ne!(0, c.shake_offset());
+        // the border blinks
+        c.update(BLINK_PERIOD, || {});
+        assert_eq!(Style::new(), c.border_style());
+
+        c.toggle_pause();
+        assert_eq!(None, c.pulse_factor());
+        assert_eq!(0, c.shake_offset());
+    }
+
+    #[test]
+    fn when_completed_should_flash_then_calm_down() {
+        let mut c = chrono(60);
+        c.update(secs(59), || {});
+        assert!(!c.flashing());
+        c.update(secs(1), || {});
+        assert!(c.flashing());
+        // past the threshold, nothing is urgent anymore
+        assert_eq!(Style::new(), c.border_style());
+        assert_eq!(0, c.shake_offset());
+        c.update(FLASH_DURATION, || {});
+        assert!(!c.flashing());
+    }
+
+    #[test]
+    fn when_reduced_motion_should_disable_the_effects() {
+        let mut c = chrono(60).with_urgency(None);
+        c.update(secs(55), || {});
+        assert_eq!(Style::new(), c.border_style());
+        assert_eq!(None, c.pulse_factor());
+        assert_eq!(0, c.shake_offset());
+        c.update(secs(5), || {});
+        assert!(!c.flashing());
+
+        let calm = Urgency {
+            pulse: false,
+            shake: false,
+            ..Urgency::default()
         };
-        let shape = shape.with_bgcolor(Color::DarkGray);
-        let marker = self.shapes_selected.get_marker();
-        let canvas = Canvas::default()
-            .block(Block::bordered())
-            .marker(marker)
-            .x_bounds([left, right])
-            .y_bounds([bottom, top])
-            .paint(move |ctx| {
-                ctx.draw(&shape);
-            });
-        canvas.render(area, buf);
+        let mut c = chrono(60).with_urgency(Some(calm));
+        c.update(secs(55), || {});
+        assert_eq!(None, c.pulse_factor());
+        assert_eq!(0, c.shake_offset());
+        assert_ne!(Style::new(), c.border_style());
+    }
+
+    #[test]
+    fn when_paused_should_keep_remaining_time() {
+        let mut c = chrono(60);
+        c.toggle_pause();
+        c.update(Duration::from_secs(10), || {});
+        assert_eq!(Duration::from_secs(60), c.remaining);
+        assert!(!c.animating());
+        c.toggle_pause();
+        c.update(Duration::from_secs(10), || {});
+        assert_eq!(Duration::from_secs(50), c.remaining);
+    }
+
+    #[test]
+    fn when_reset_should_restore_original_and_keep_pause() {
+        let mut c = chrono(60);
+        c.increase_timeout(secs(60));
+        c.update(Duration::from_secs(30), || {});
+        c.toggle_pause();
+        c.reset();
+        assert_eq!(Duration::from_secs(60), c.timeout);
+        assert_eq!(Duration::from_secs(60), c.remaining);
+        assert!(c.paused());
+        c.restart();
+        assert!(!c.paused());
+        assert_eq!(Duration::from_secs(60), c.original());
+    }
+
+    #[test]
+    fn when_completed_should_restart_or_repeat_last() {
+        let done = Cell::new(0);
+        let mut c = chrono(60);
+        c.increase_timeout(secs(60));
+        c.update(Duration::from_secs(120), || done.set(done.get() + 1));
+        assert_eq!(1, done.get());
+
+        c.repeat_last();
+        assert_eq!(Duration::from_secs(120), c.remaining);
+        c.update(Duration::from_secs(120), || done.set(done.get() + 1));
+        assert_eq!(2, done.get());
+
+        c.restart();
+        assert_eq!(Duration::from_secs(60), c.remaining);
+        assert!(!c.flashing());
+        c.update(Duration::from_secs(60), || done.set(done.get() + 1));
+        assert_eq!(3, done.get());
+        // nothing completed yet, repeat the original
+        let mut fresh = chrono(30);
+        fresh.decrease_timeout(secs(10));
+        fresh.repeat_last();
+        assert_eq!(Duration::from_secs(30), fresh.remaining);
+    }
+
+    #[test]
+    fn when_snoozed_should_count_down_again_from_the_clock() {
+        let manual = clock::ManualClock::default();
+        let mut c = chrono(60).with_clock(std::sync::Arc::new(manual.clone()));
+        let done = Cell::new(0);
+        c.snooze(secs(300));
+        assert_eq!(0, c.snoozes()); // still running
+
+        manual.advance(secs(45));
+        c.tick(|| done.set(done.get() + 1));
+        assert_eq!(secs(15), c.remaining);
+        manual.advance(secs(15));
+        c.tick(|| done.set(done.get() + 1));
+        assert_eq!(1, done.get());
+
+        c.snooze(secs(300));
+        assert_eq!(1, c.snoozes());
+        assert_eq!(secs(300), c.remaining);
+        manual.advance(secs(300));
+        c.tick(|| done.set(done.get() + 1));
+        assert_eq!(2, done.get());
+        c.snooze(secs(300));
+        assert_eq!(2, c.snoozes());
+
+        // the snoozes are not what a repeat runs
+        c.repeat_last();
+        assert_eq!(0, c.snoozes());
+        assert_eq!(secs(60), c.remaining);
+    }
+
+    #[test]
+    fn when_decreasing_should_keep_elapsed_time() {
+        let mut c = chrono(600);
+        c.update(secs(100), || {});
+        c.decrease_timeout(secs(60));
+        assert_eq!(secs(540), c.timeout);
+        assert_eq!(secs(440), c.remaining);
+    }
+
+    #[test]
+    fn when_adjusting_should_change_what_the_mode_selects() {
+        // (mode, step in seconds, (timeout, remaining))
+        let cases = [
+            (Adjust::Both, 10, (70, 40)),
+            (Adjust::Both, -10, (50, 20)),
+            (Adjust::Remaining, 10, (60, 40)),
+            (Adjust::Remaining, 40, (70, 70)),
+            (Adjust::Remaining, -10, (60, 20)),
+            (Adjust::Total, 10, (70, 30)),
+            (Adjust::Total, -10, (50, 30)),
+            (Adjust::Total, -40, (30, 30)),
+        ];
+        for (adjust, step, expected) in cases {
+            let f = |c: &mut AnimChrono| match step {
+                i64::MIN..0 => c.decrease_timeout(secs(step.unsigned_abs())),
+                _ => c.increase_timeout(secs(step as u64)),
+            };
+            assert_eq!(expected, adjusted(adjust, f), "{adjust:?} {step}");
+        }
+    }
+
+    #[test]
+    fn when_decreasing_near_zero_should_saturate_and_complete() {
+        let subs: [fn(&mut AnimChrono); 2] = [
+            |c| c.decrease_timeout(secs(45)),
+            |c| c.decrease_timeout(secs(3600)),
+        ];
+        for adjust in Adjust::value_variants() {
+            for f in subs {
+                let (timeout, remaining) = adjusted(*adjust, f);
+                assert!(remaining <= timeout, "{adjust:?}");
+                if *adjust != Adjust::Total {
+                    assert_eq!(0, remaining, "{adjust:?}");
+                }
+            }
+        }
+        let done = Cell::new(0);
+        let mut c = chrono(60);
+        c.update(secs(59), || {});
+        c.decrease_timeout(secs(1));
+        assert!(c.progress() <= 1.0);
+        c.update(Duration::from_millis(1), || done.set(done.get() + 1));
+        assert_eq!(1, done.get());
+        // nothing left to remove
+        c.decrease_timeout(secs(1));
+        c.decrease_timeout(secs(3600));
+        assert_eq!(Duration::ZERO, c.remaining);
+        assert_eq!(1.0, c.progress());
+        c.update(secs(1), || done.set(done.get() + 1));
+        assert_eq!(1, done.get());
+    }
+
+    #[test]
+    fn when_increasing_after_completion_should_run_again() {
+        for adjust in Adjust::value_variants() {
+            let done = Cell::new(0);
+            let mut c = chrono(10).with_adjust(*adjust);
+            c.update(secs(10), || done.set(done.get() + 1));
+            c.increase_timeout(secs(5));
+            c.update(secs(5), || done.set(done.get() + 1));
+            // a longer total alone leaves nothing to count down
+            let expected = if *adjust == Adjust::Total { 1 } else { 2 };
+            assert_eq!(expected, done.get(), "{adjust:?}");
+            assert!(c.remaining <= c.timeout, "{adjust:?}");
+        }
+        // less than a second is already complete
+        let done = Cell::new(0);
+        let mut c = chrono(10);
+        c.update(secs(10), || done.set(done.get() + 1));
+        c.increase_timeout(Duration::from_millis(500));
+        c.update(Duration::from_millis(500), || done.set(done.get() + 1));
+        assert_eq!(1, done.get());
+    }
+
+    #[test]
+    fn when_timeout_is_zero_should_show_complete_progress() {
+        let mut c = chrono(5);
+        c.decrease_timeout(secs(5));
+        assert_eq!(Duration::ZERO, c.timeout);
+        assert_eq!(1.0, c.progress());
+        c.increase_timeout(secs(5));
+        assert_eq!(0.0, c.progress());
+    }
+
+    #[test]
+    fn when_pointing_at_the_arc_should_read_the_dial() {
+        let c = chrono(60);
+        assert_eq!(None, c.dial_progress(Position::new(1, 1)));
+        let area = Rect::new(0, 0, 42, 22);
+        c.render(area, &mut Buffer::empty(area));
+        // right, top, left and bottom of the circle
+        for (x, y, expected) in [(39, 10, 0.0), (21, 1, 0.25), (1, 10, 0.5), (21, 20, 0.75)] {
+            let p = c.dial_progress(Position::new(x, y)).unwrap();
+            assert!((p - expected).abs() < 0.02, "({x}, {y}) {p}");
+        }
+        assert_eq!(None, c.dial_progress(Position::new(0, 0)));
+        assert_eq!(None, c.dial_progress(Position::new(50, 10)));
+
+        let spiral = AnimChrono::new(ShapeSelect::select_from(1, Color::Red), secs(60));
+        spiral.render(area, &mut Buffer::empty(area));
+        assert_eq!(None, spiral.dial_progress(Position::new(21, 1)));
+    }
+
+    #[test]
+    fn when_dial_turned_should_set_remaining_and_run_again() {
+        let done = Cell::new(0);
+        let mut c = chrono(60);
+        c.set_progress(0.25);
+        assert_eq!(secs(45), c.remaining);
+        assert_eq!(0.25, c.displayed_progress());
+        c.update(secs(45), || done.set(done.get() + 1));
+        assert_eq!(1, done.get());
+        c.set_progress(0.5);
+        assert_eq!(secs(30), c.remaining);
+        c.update(secs(30), || done.set(done.get() + 1));
+        assert_eq!(2, done.get());
+        c.set_progress(2.0);
+        assert_eq!(Duration::ZERO, c.remaining);
+    }
+
+    #[test]
+    fn when_canvas_marker_or_shape_changes_should_trace_again() {
+        // the traced path and what it was traced for
+        fn traced(c: &AnimChrono, area: Rect) -> (Option<(u16, u16, symbols::Marker)>, usize) {
+            c.render(area, &mut Buffer::empty(area));
+            let cache = c.cache.borrow();
+            (cache.key, cache.cells.len())
+        }
+        let mut c = chrono(60);
+        let small = Rect::new(0, 0, 42, 22);
+        let (key, len) = traced(&c, small);
+        assert_eq!(Some((40, 20)), key.map(|(w, h, _)| (w, h)));
+        assert!(len > 0);
+        c.update(secs(30), || {});
+        assert_eq!(
+            (key, len),
+            traced(&c, small),
+            "progress alone keeps the path"
+        );
+
+        let large = Rect::new(0, 0, 82, 42);
+        let (resized, resized_len) = traced(&c, large);
+        assert_eq!(Some((80, 40)), resized.map(|(w, h, _)| (w, h)));
+        assert!(resized_len > len);
+
+        c.markers.forced = Some(MarkerKind::Block);
+        let (blocks, _) = traced(&c, large);
+        assert_eq!(Some(symbols::Marker::Block), blocks.map(|(.., m)| m));
+        assert_ne!(resized, blocks);
+
+        c.set_shape(ShapeSelect::select_from(1, Color::Red));
+        assert_eq!(None, c.cache.borrow().key);
+        let (key, spiral_len) = traced(&c, large);
+        assert_eq!(blocks, key);
+        assert_ne!(resized_len, spiral_len);
+    }
+
+    #[test]
+    fn when_progress_moves_should_repaint_as_a_full_canvas_would() {
+        let gradient = Gradient::evenly(&[Color::Rgb(0, 200, 0), Color::Rgb(200, 0, 0)]);
+        let area = Rect::new(0, 0, 37, 17);
+        for kind in [
+            MarkerKind::Braille,
+            MarkerKind::HalfBlock,
+            MarkerKind::Block,
+        ] {
+            for select in 0..ShapeSelect::COUNT {
+                let shape = ShapeSelect::select_from(select, Color::Red);
+                if shape.look().is_none() {
+                    continue;
+                }
+                let mut c =
+                    AnimChrono::new(shape, secs(60)).with_colors(Color::Red, Color::DarkGray);
+                // plain colour, along the path, following the progress
+                match select % 3 {
+                    0 => {}
+                    n => c = c.with_gradient(gradient.clone(), n == 1),
+                }
+                c.markers.forced = Some(kind);
+                for progress in [0.0, 0.1, 0.13, 0.5, 0.45, 0.9, 1.0, 0.2] {
+                    c.set_progress(progress);
+                    let mut patched = Buffer::empty(area);
+                    c.render(area, &mut patched);
+                    c.cache.borrow_mut().painted = None;
+                    let mut full = Buffer::empty(area);
+                    c.render(area, &mut full);
+                    assert_eq!(full, patched, "{} {kind:?} at {progress}", c.shape_name());
+                }
+            }
+        }
     }
 }
@@ -1,135 +1,737 @@
 use crate::anime;
+use crate::cli;
+use crate::clock::SharedClock;
+use crate::config;
 use crate::fps;
+use crate::image::Image;
+use crate::keymap::{self, Action};
 use crate::shapes;
+use crate::sound;
+use crate::spoty;
 use crate::theme;
 use color_eyre::Result;
 use rand::Rng;
 use ratatui::{
-    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
-    layout::{self, Constraint, Layout},
+    buffer::Buffer,
+    crossterm::{
+        event::{
+            DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture, Event,
+            KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+        },
+        execute,
+    },
+    layout::{self, Constraint, Layout, Position, Rect},
     style::{Color, Style, Stylize},
     symbols::border,
     text::{Line, Span, ToSpan},
-    widgets::{block::Title, Block, Widget},
+    widgets::{block::Title, Block, Clear, List, ListItem, ListState, Paragraph, Widget, Wrap},
     DefaultTerminal, Frame,
 };
 
-use crate::music_player;
-use rust_embed::RustEmbed;
-use std::time::{Duration, Instant};
-
-#[derive(RustEmbed)]
-#[folder = "assets/"]
-struct Asset;
+use crate::event::{AppEvent, EventBus};
+use crate::ipc::{self, IpcCommand};
+use crate::music_player::{self, SpotifyTask};
+use crate::palette::{Command, Palette, PaletteEvent};
+use crate::prompt::{self, DurationPrompt, PromptEvent};
+use std::{
+    io::stdout,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
 #[derive(Debug, PartialEq)]
 enum AppState {
     Main,
     CmdSelect,
+    Presets,
+    Prompt,
+    Palette,
     Quit,
 }
 
+/// Messages kept for the logs overlay.
+const LOG_LINES: usize = 100;
+
 pub struct App {
+    clock: SharedClock,
     fps: fps::Fps,
+    pacer: fps::FramePacer,
+    show_perf: bool,
+    show_help: bool,
+    show_logs: bool,
+    log: Vec<String>, // oldest first
+    keymap: keymap::Keymap,
+    steps: anime::Steps,
     tm_animation: anime::AnimChrono,
     player: music_player::MusicPlayer,
     state: AppState,
+    theme: theme::Theme,
+    theme_watcher: Option<theme::ThemeWatcher>,
+    color_depth: theme::ColorDepth,
+    preset: config::Preset, // the running timer
+    presets: Vec<config::Preset>,
+    preset_list: ListState,
+    preset_area: Rect, // rows of the preset list in the last render
+    title_bar: Rect,
+    mouse: bool,
+    events: EventBus,
+    ipc: Option<PathBuf>, // socket taking commands
+    dial: Option<f64>,    // last progress while the arc is dragged
+    prompt: DurationPrompt,
+    palette: Palette,
+    custom: Vec<shapes::Custom>,
+    gradient: Option<(shapes::Gradient, bool)>, // kept for the shapes picked later
+    status: Option<String>, // shown in place of the terminal size, e.g. theme errors
 }
 
-const DARK_BLUE: Color = Color::Rgb(16, 24, 48);
-
 impl App {
     //TODO: timeout should be an option, don't play animation of None
-    pub fn new(timeout: Duration) -> Self {
-        //TODO: move this to main should come from user config
-        let rand_select = rand::thread_rng().gen_range(0..3);
-        let s = shapes::ShapeSelect::select_from(rand_select, Color::LightRed);
+    pub fn new(preset: config::Preset) -> Self {
+        let theme = theme::Theme::default();
+        let s = preset
+            .shape
+            .as_deref()
+            .and_then(|name| shapes::ShapeSelect::find(name, &[], theme.canvas.fg))
+            .unwrap_or_else(|| random_shape(&[], theme.canvas.fg));
+        let label = if preset.name.is_empty() {
+            cli::format_duration(&preset.duration)
+        } else {
+            preset.name.clone()
+        };
 
         Self {
+            clock: crate::clock::system(),
             fps: fps::Fps::default(),
-            tm_animation: anime::AnimChrono::new(s, timeout),
+            pacer: fps::FramePacer::default(),
+            show_perf: false,
+            show_help: false,
+            show_logs: false,
+            log: vec![],
+            keymap: keymap::Keymap::default(),
+            steps: anime::Steps::default(),
+            tm_animation: anime::AnimChrono::new(s, preset.duration)
+                .with_label(label)
+                .with_colors(theme.canvas.fg, theme.canvas.bg),
             player: music_player::MusicPlayer::default(),
             state: AppState::Main,
+            theme,
+            theme_watcher: None,
+            color_depth: theme::ColorDepth::default(),
+            preset,
+            presets: vec![],
+            preset_list: ListState::default(),
+            preset_area: Rect::default(),
+            title_bar: Rect::default(),
+            mouse: true,
+            events: EventBus::default(),
+            ipc: None,
+            dial: None,
+            prompt: DurationPrompt::default(),
+            palette: Palette::default(),
+            custom: vec![],
+            gradient: None,
+            status: None,
         }
     }
 
-    fn handle_event_main(&mut self, key: KeyEvent) {
-        match key.code {
-            KeyCode::Char('q') => {
-                self.state = AppState::Quit;
+    pub fn with_theme(mut self, theme: theme::Theme) -> Self {
+        self.set_theme(theme);
+        self
+    }
+
+    /// Maps every colour drawn to what the terminal can display.
+    pub fn with_color_depth(mut self, depth: theme::ColorDepth) -> Self {
+        self.color_depth = depth;
+        self
+    }
+
+    /// Times the countdown and the frames with `clock`.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.fps = fps::Fps::with_clock(clock.clone());
+        self.tm_animation = self.tm_animation.with_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+
+    /// Captures the mouse, which also stops the terminal selecting text.
+    pub fn with_mouse(mut self, mouse: bool) -> Self {
+        self.mouse = mouse;
+        self
+    }
+
+    /// Takes commands such as `pause` or `set 25m` on the unix socket `path`.
+    pub fn with_ipc(mut self, path: PathBuf) -> Self {
+        self.ipc = Some(path);
+        self
+    }
+
+    /// Reloads the theme when the watched file changes.
+    pub fn with_theme_watcher(mut self, watcher: theme::ThemeWatcher) -> Self {
+        self.theme_watcher = Some(watcher);
+        self
+    }
+
+    fn set_theme(&mut self, theme: theme::Theme) {
+        self.tm_animation
+            .set_colors(theme.canvas.fg, theme.canvas.bg);
+        self.player.set_theme(theme.player.clone());
+        self.prompt.set_theme(theme.popup.clone());
+        self.palette.set_theme(theme.popup.clone());
+        self.theme = theme;
+    }
+
+    fn reload_theme(&mut self) {
+        let Some(result) = self.theme_watcher.as_mut().and_then(|w| w.poll()) else {
+            return;
+        };
+        match result {
+            Ok(theme) => {
+                self.set_theme(theme);
+                self.status = None;
             }
-            KeyCode::Down | KeyCode::Char('j') => {
-                self.tm_animation.decrease_timeout(60);
+            // keep the previous theme while the file is being edited
+            Err(e) => {
+                self.status = Some(format!("theme: {e}"));
+                self.log(format!("theme: {e}"));
             }
-            KeyCode::Up | KeyCode::Char('k') => {
-                self.tm_animation.increase_timeout(60);
+        }
+    }
+
+    fn log(&mut self, message: String) {
+        self.log.push(message);
+        if self.log.len() > LOG_LINES {
+            self.log.remove(0);
+        }
+    }
+
+    pub fn with_frame_rate(mut self, frame_rate: f64, mode: fps::FrameMode) -> Self {
+        self.pacer = fps::FramePacer::new(frame_rate, mode);
+        self
+    }
+
+    pub fn with_gradient(mut self, gradient: shapes::Gradient, along_path: bool) -> Self {
+        self.tm_animation = self
+            .tm_animation
+            .with_gradient(gradient.clone(), along_path);
+        self.gradient = Some((gradient, along_path));
+        self
+    }
+
+    pub fn with_keymap(mut self, keymap: keymap::Keymap) -> Self {
+        self.keymap = keymap;
+        self
+    }
+
+    /// Presets offered in the preset menu.
+    pub fn with_presets(mut self, presets: Vec<config::Preset>) -> Self {
+        self.prompt
+            .set_presets(presets.iter().map(|p| p.name.clone()).collect());
+        self.presets = presets;
+        self
+    }
+
+    pub fn with_spotify(mut self, settings: &spoty::Settings) -> Self {
+        self.player.set_spotify(settings);
+        self
+    }
+
+    /// Animates `shape` instead of a random one.
+    pub fn with_shape(mut self, shape: shapes::ShapeSelect) -> Self {
+        self.set_shape(shape);
+        self
+    }
+
+    pub fn with_sound(mut self, sound: sound::Sound) -> Self {
+        self.preset.sound = sound;
+        self
+    }
+
+    /// Adds the user shapes to the random pick, and to the shapes presets can name.
+    pub fn with_custom_shapes(mut self, custom: Vec<shapes::Custom>) -> Self {
+        self.custom = custom;
+        if self.preset.shape.is_none() {
+            let shape = random_shape(&self.custom, self.theme.canvas.fg);
+            self.set_shape(shape);
+        }
+        self
+    }
+
+    fn set_shape(&mut self, shape: shapes::ShapeSelect) {
+        let shape = match &self.gradient {
+            Some((g, along)) => shape.with_gradient(g.clone(), *along),
+            None => shape,
+        };
+        self.tm_animation.set_shape(shape);
+        self.tm_animation
+            .set_colors(self.theme.canvas.fg, self.theme.canvas.bg);
+    }
+
+    /// Restarts the countdown with `preset`, the shape only changes if the preset names one.
+    fn start_preset(&mut self, preset: config::Preset) {
+        let fg = self.theme.canvas.fg;
+        let shape = preset
+            .shape
+            .as_deref()
+            .and_then(|name| shapes::ShapeSelect::find(name, &self.custom, fg));
+        if let Some(shape) = shape {
+            self.set_shape(shape);
+        }
+        self.tm_animation.start(preset.duration);
+        self.preset = preset;
+    }
+
+    /// Reveals `image` instead of the random shape.
+    pub fn with_image(mut self, image: Image, reveal: shapes::Reveal, invert: bool) -> Self {
+        let mask = shapes::Mask::new(image, reveal, self.theme.canvas.fg).with_invert(invert);
+        self.tm_animation = self
+            .tm_animation
+            .with_shape(shapes::ShapeSelect::MaskSelect(mask));
+        self
+    }
+
+    pub fn with_markers(mut self, markers: crate::marker::MarkerPolicy) -> Self {
+        self.tm_animation = self.tm_animation.with_markers(markers);
+        self
+    }
+
+    pub fn with_urgency(mut self, urgency: Option<anime::Urgency>) -> Self {
+        self.tm_animation = self.tm_animation.with_urgency(urgency);
+        self
+    }
+
+    /// Time added or removed by the timeout keys.
+    pub fn with_steps(mut self, steps: anime::Steps) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    pub fn with_adjust(mut self, adjust: anime::Adjust) -> Self {
+        self.tm_animation = self.tm_animation.with_adjust(adjust);
+        self
+    }
+
+    pub fn with_easing(mut self, easing: anime::Easing) -> Self {
+        self.tm_animation = self.tm_animation.with_easing(easing);
+        self
+    }
+
+    fn handle_action_main(&mut self, action: Action) {
+        match action {
+            Action::Quit => self.state = AppState::Quit,
+            Action::AddSmall => self.tm_animation.increase_timeout(self.steps.small),
+            Action::SubSmall => self.tm_animation.decrease_timeout(self.steps.small),
+            Action::AddMedium => self.tm_animation.increase_timeout(self.steps.medium),
+            Action::SubMedium => self.tm_animation.decrease_timeout(self.steps.medium),
+            Action::AddLarge => self.tm_animation.increase_timeout(self.steps.large),
+            Action::SubLarge => self.tm_animation.decrease_timeout(self.steps.large),
+            Action::Pause => self.tm_animation.toggle_pause(),
+            Action::Reset => self.tm_animation.reset(),
+            Action::Restart => self.tm_animation.restart(),
+            Action::RepeatLast => self.tm_animation.repeat_last(),
+            Action::Snooze => self.tm_animation.snooze(self.steps.snooze),
+            Action::MusicPlayer => self.state = AppState::CmdSelect,
+            Action::Palette => {
+                self.palette.open(self.palette_commands());
+                self.state = AppState::Palette;
+            }
+            Action::EditDuration => {
+                self.prompt.open();
+                self.state = AppState::Prompt;
             }
-            KeyCode::Right | KeyCode::Char('l') => {
-                self.tm_animation.increase_timeout(1);
+            Action::Presets => {
+                self.state = AppState::Presets;
+                if self.preset_list.selected().is_none() {
+                    self.preset_list.select_first();
+                }
             }
-            KeyCode::Left | KeyCode::Char('h') => {
-                self.tm_animation.decrease_timeout(1);
+            _ => {}
+        }
+    }
+
+    fn handle_action_player(&mut self, action: Action) {
+        match action {
+            Action::Back => self.state = AppState::Main,
+            Action::Deselect => self.player.select_none(),
+            Action::Down => self.player.select_next(),
+            Action::Up => self.player.select_previous(),
+            Action::First => self.player.select_first(),
+            Action::Last => self.player.select_last(),
+            Action::Select => {
+                if let Some(task) = self.player.do_action() {
+                    self.spawn(task);
+                }
             }
-            KeyCode::Char('p') => {
-                if key.modifiers == KeyModifiers::CONTROL {
-                    self.state = AppState::CmdSelect;
+            _ => {}
+        }
+    }
+
+    fn handle_action_presets(&mut self, action: Action) {
+        match action {
+            Action::Back => self.state = AppState::Main,
+            Action::Deselect => self.preset_list.select(None),
+            Action::Down => self.preset_list.select_next(),
+            Action::Up => self.preset_list.select_previous(),
+            Action::First => self.preset_list.select_first(),
+            Action::Last => self.preset_list.select_last(),
+            Action::Select => {
+                let selected = self
+                    .preset_list
+                    .selected()
+                    .and_then(|i| self.presets.get(i));
+                if let Some(preset) = selected.cloned() {
+                    self.start_preset(preset);
+                    self.state = AppState::Main;
                 }
             }
             _ => {}
         }
     }
 
-    async fn handle_event_player(&mut self, key: KeyEvent) {
-        if key.kind != KeyEventKind::Press {
+    // keys are typed in the prompt rather than looked up in the keymap
+    fn handle_prompt(&mut self, key: KeyEvent) {
+        match self.prompt.on_key_press(key) {
+            PromptEvent::Editing => return,
+            PromptEvent::Cancel => {}
+            PromptEvent::Submit(prompt::Entry::Duration(d)) => self.tm_animation.start(d),
+            PromptEvent::Submit(prompt::Entry::Preset(name)) => {
+                if let Some(preset) = self.presets.iter().find(|p| p.name == name).cloned() {
+                    self.start_preset(preset);
+                }
+            }
+        }
+        self.state = AppState::Main;
+    }
+
+    // every action of the timer, then what has no key
+    fn palette_commands(&self) -> Vec<(Command, String)> {
+        let actions = Action::ALL
+            .into_iter()
+            .filter(|a| a.context() != keymap::Context::List && *a != Action::Palette);
+        let mut commands: Vec<Command> = actions.map(Command::Action).collect();
+        commands.extend(
+            shapes::ShapeSelect::names(&self.custom)
+                .into_iter()
+                .map(Command::Shape),
+        );
+        commands.extend(
+            theme::Theme::BUILTIN
+                .iter()
+                .map(|(name, _)| Command::Theme(name.to_string())),
+        );
+        commands.extend(self.presets.iter().map(|p| Command::Preset(p.name.clone())));
+        commands.push(Command::ConnectSpotify);
+        commands.push(Command::Logs);
+        commands
+            .into_iter()
+            .map(|c| {
+                let keys = match &c {
+                    Command::Action(a) => self
+                        .keymap
+                        .keys(*a)
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("/"),
+                    _ => String::new(),
+                };
+                (c, keys)
+            })
+            .collect()
+    }
+
+    fn handle_palette(&mut self, key: KeyEvent) {
+        match self.palette.on_key_press(key) {
+            PaletteEvent::Editing => {}
+            PaletteEvent::Cancel => self.state = AppState::Main,
+            PaletteEvent::Run(command) => {
+                self.state = AppState::Main;
+                self.run_command(command);
+            }
+        }
+    }
+
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::Action(action) => self.dispatch(action),
+            Command::Shape(name) => {
+                let fg = self.theme.canvas.fg;
+                if let Some(shape) = shapes::ShapeSelect::find(&name, &self.custom, fg) {
+                    self.log(format!("shape: {name}"));
+                    self.set_shape(shape);
+                }
+            }
+            Command::Theme(name) => {
+                if let Some(theme) = theme::Theme::builtin(&name) {
+                    // the watched file would take over again on its next change
+                    self.theme_watcher = None;
+                    self.status = None;
+                    self.set_theme(theme);
+                    self.log(format!("theme: {name}"));
+                }
+            }
+            Command::Preset(name) => {
+                if let Some(preset) = self.presets.iter().find(|p| p.name == name).cloned() {
+                    self.start_preset(preset);
+                }
+            }
+            Command::ConnectSpotify => {
+                self.state = AppState::CmdSelect;
+                if let Some(task) = self.player.connect_spotify() {
+                    self.spawn(task);
+                }
+            }
+            Command::Logs => self.show_logs = true,
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        let pos = Position::new(mouse.column, mouse.row);
+        if self.show_help
+            || self.show_logs
+            || matches!(self.state, AppState::Prompt | AppState::Palette)
+        {
             return;
         }
-        match key.code {
-            KeyCode::Char('q') => self.state = AppState::Main,
-            KeyCode::Char('h') | KeyCode::Left => self.player.select_none(),
-            KeyCode::Char('j') | KeyCode::Down => self.player.select_next(),
-            KeyCode::Char('k') | KeyCode::Up => self.player.select_previous(),
-            KeyCode::Char('g') | KeyCode::Home => self.player.select_first(),
-            KeyCode::Char('G') | KeyCode::End => self.player.select_last(),
-            KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter => {
-                self.player.do_action().await;
-                // self.player.enter_command();
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) if self.title_bar.contains(pos) => {
+                self.tm_animation.toggle_pause()
+            }
+            MouseEventKind::Down(MouseButton::Left) => match self.tm_animation.dial_progress(pos) {
+                Some(progress) => self.turn_dial(progress),
+                None => self.click_list(pos),
+            },
+            MouseEventKind::Drag(MouseButton::Left) if self.dial.is_some() => {
+                if let Some(progress) = self.tm_animation.dial_progress(pos) {
+                    self.turn_dial(progress);
+                }
             }
+            MouseEventKind::Up(MouseButton::Left) => self.dial = None,
+            MouseEventKind::ScrollUp => self.scroll(pos, mouse.modifiers, true),
+            MouseEventKind::ScrollDown => self.scroll(pos, mouse.modifiers, false),
             _ => {}
         }
     }
 
-    async fn handle_event(&mut self, key: KeyEvent) {
-        match self.state {
-            AppState::Main => {
-                self.handle_event_main(key);
+    // like a kitchen timer, the dial stops at the ends rather than going round
+    fn turn_dial(&mut self, progress: f64) {
+        let progress = match self.dial {
+            Some(last) if (progress - last).abs() > 0.5 => {
+                if last > 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
             }
+            _ => progress,
+        };
+        self.dial = Some(progress);
+        self.tm_animation.set_progress(progress);
+    }
+
+    // the first click selects a row, a click on the selected row acts on it
+    fn click_list(&mut self, pos: Position) {
+        match self.state {
             AppState::CmdSelect => {
-                self.handle_event_player(key).await;
+                if let Some(i) = self.player.row_at(pos) {
+                    if self.player.selected() == Some(i) {
+                        if let Some(task) = self.player.do_action() {
+                            self.spawn(task);
+                        }
+                    } else {
+                        self.player.select(i);
+                    }
+                }
+            }
+            AppState::Presets => {
+                let offset = self.preset_list.offset();
+                if let Some(i) = list_row(self.preset_area, offset, self.presets.len(), pos) {
+                    if self.preset_list.selected() == Some(i) {
+                        self.handle_action_presets(Action::Select);
+                    } else {
+                        self.preset_list.select(Some(i));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // over the shape it changes the time, shift for the large step, over a list it moves
+    fn scroll(&mut self, pos: Position, modifiers: KeyModifiers, up: bool) {
+        if self.tm_animation.area().contains(pos) {
+            let step = if modifiers.contains(KeyModifiers::SHIFT) {
+                self.steps.large
+            } else {
+                self.steps.medium
+            };
+            if up {
+                self.tm_animation.increase_timeout(step);
+            } else {
+                self.tm_animation.decrease_timeout(step);
+            }
+            return;
+        }
+        match (&self.state, up) {
+            (AppState::CmdSelect, true) => self.player.select_previous(),
+            (AppState::CmdSelect, false) => self.player.select_next(),
+            (AppState::Presets, true) if self.preset_area.contains(pos) => {
+                self.preset_list.select_previous()
+            }
+            (AppState::Presets, false) if self.preset_area.contains(pos) => {
+                self.preset_list.select_next()
+            }
+            _ => {}
+        }
+    }
+
+    fn context(&self) -> keymap::Context {
+        match self.state {
+            AppState::Main | AppState::Prompt | AppState::Palette | AppState::Quit => {
+                keymap::Context::Main
             }
+            AppState::CmdSelect | AppState::Presets => keymap::Context::List,
+        }
+    }
+
+    fn handle_event(&mut self, key: KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        // any key closes the help and the logs
+        if self.show_help || self.show_logs {
+            self.show_help = false;
+            self.show_logs = false;
+            return;
+        }
+        match self.state {
+            AppState::Prompt => return self.handle_prompt(key),
+            AppState::Palette => return self.handle_palette(key),
             _ => {}
         }
+        if let Some(action) = self.keymap.action(self.context(), &key) {
+            self.dispatch(action);
+        }
+    }
+
+    // `action` where the app is, from a key, the palette or the IPC socket
+    fn dispatch(&mut self, action: Action) {
+        match (action, &self.state) {
+            (Action::Perf, _) => self.show_perf = !self.show_perf,
+            (Action::Help, _) => self.show_help = true,
+            (_, AppState::Main) => self.handle_action_main(action),
+            (_, AppState::CmdSelect) => self.handle_action_player(action),
+            (_, AppState::Presets) => self.handle_action_presets(action),
+            (_, AppState::Prompt | AppState::Palette | AppState::Quit) => {}
+        }
+    }
+
+    /// Reacts to one event of the bus, long-running work goes to tasks
+    /// reporting back with another event.
+    pub fn update(&mut self, event: AppEvent) {
+        match event {
+            AppEvent::Input(Event::Key(key)) => self.handle_event(key),
+            AppEvent::Input(Event::Mouse(mouse)) => self.handle_mouse(mouse),
+            AppEvent::Input(Event::FocusGained) => self.pacer.set_focused(true),
+            AppEvent::Input(Event::FocusLost) => self.pacer.set_focused(false),
+            // a resize is caught by the next draw
+            AppEvent::Input(_) => {}
+            // the countdown moves in `tick`, once the frame is due
+            AppEvent::Tick => {}
+            AppEvent::Audio(Ok(())) => {}
+            AppEvent::Audio(Err(e)) => self.log(format!("sound: {e}")),
+            AppEvent::Spotify(result) => {
+                if let Some(message) = self.player.on_result(result) {
+                    self.log(message);
+                }
+            }
+            AppEvent::Ipc(IpcCommand::Action(action)) => self.dispatch(action),
+            AppEvent::Ipc(IpcCommand::Set(d)) => self.tm_animation.start(d),
+            AppEvent::InputClosed(error) => {
+                if let Some(e) = error {
+                    self.log(format!("terminal: {e}"));
+                }
+                self.state = AppState::Quit;
+            }
+        }
+    }
+
+    /// Moves the countdown to the time of the clock, the completion sound
+    /// reports on the bus.
+    pub fn tick(&mut self) {
+        let tx = self.events.sender();
+        let sound = &self.preset.sound;
+        self.tm_animation.tick(|| {
+            let tx = tx.clone();
+            sound.play_then(move |r| {
+                let _ = tx.send(AppEvent::Audio(r));
+            });
+        });
+    }
+
+    // runs `task` in the background, its result comes back as an event
+    fn spawn(&self, task: SpotifyTask) {
+        let tx = self.events.sender();
+        tokio::spawn(async move {
+            let _ = tx.send(AppEvent::Spotify(task.await));
+        });
     }
 
     pub async fn run(&mut self, mut terminal: DefaultTerminal) -> Result<()> {
-        let tick_rate = Duration::from_millis(16);
-        let mut last_tick = Instant::now();
+        // the socket is only removed on exit once this run created it
+        let socket = match self.ipc.clone() {
+            Some(path) => {
+                ipc::listen(path.clone(), self.events.sender())?;
+                Some(path)
+            }
+            None => None,
+        };
+        execute!(stdout(), EnableFocusChange)?;
+        if self.mouse {
+            execute!(stdout(), EnableMouseCapture)?;
+        }
+        self.events.listen_terminal();
+        let result = self.run_loop(&mut terminal).await;
+        if let Some(path) = socket {
+            let _ = std::fs::remove_file(path);
+        }
+        if self.mouse {
+            execute!(stdout(), DisableMouseCapture)?;
+        }
+        execute!(stdout(), DisableFocusChange)?;
+        result
+    }
+
+    async fn run_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        let mut last_tick = self.clock.now();
         while self.state != AppState::Quit {
-            self.fps.update();
+            let mut timing = fps::FrameTiming::default();
+            self.reload_theme();
+            let frame_start = Instant::now();
             terminal.draw(|frame| self.draw(frame))?;
-            // timeout = 16 - loop_interval
-            let interval_tick = tick_rate.saturating_sub(last_tick.elapsed());
-            if event::poll(interval_tick)? {
-                // block for 16ms
-                if let Event::Key(key) = event::read()? {
-                    self.handle_event(key).await;
-                }
+            timing.draw = frame_start.elapsed();
+            timing.shape = self.tm_animation.draw_time();
+            self.fps.update(timing.draw);
+
+            self.pacer.set_idle(!self.tm_animation.animating());
+            let tick_rate = self.pacer.frame_time();
+            // wait for an event until the next frame is due
+            // at most a frame, the wall clock can go back
+            let wait = (last_tick + tick_rate).saturating_sub(self.clock.now());
+            let event = self.events.next(wait.min(tick_rate)).await;
+            if !matches!(event, AppEvent::Tick) {
+                let event_start = Instant::now();
+                self.update(event);
+                timing.event = event_start.elapsed();
             }
 
-            let elapsed = last_tick.elapsed();
-            if elapsed >= tick_rate {
-                last_tick = Instant::now();
-                self.tm_animation.update(elapsed, timeout_complete);
+            let now = self.clock.now();
+            if now.saturating_sub(last_tick) >= tick_rate || now < last_tick {
+                last_tick = now;
+                let update_start = Instant::now();
+                self.tick();
+                timing.update = update_start.elapsed();
             }
+            self.fps.record(timing, self.tm_animation.shape_name());
         }
         Ok(())
     }
@@ -138,33 +740,34 @@ impl App {
         let timeout_rem = get_time_left_formated(&self.tm_animation.remaining);
         let timeout_total = get_time_left_formated(&self.tm_animation.timeout);
 
-        let complete_perc = self.tm_animation.remaining.as_millis() as f64
-            / self.tm_animation.timeout.as_millis() as f64;
-        let complete_perc = format!("{:.3}%", (1.0 - complete_perc) * 100.0);
+        let complete_perc = format!("{:.3}%", self.tm_animation.progress() * 100.0);
         let keys = [
             ("Time Left", timeout_rem.as_str()),
             ("Total Duration", timeout_total.as_str()),
         ];
-        let key_style = Style::new().fg(theme::BLACK).bg(theme::DARK_GRAY);
-        let val_style = Style::new().fg(theme::DARK_GRAY).bg(theme::BLACK);
+        let title_bar = &self.theme.title_bar;
         let mut spans: Vec<Span> = keys
             .iter()
             .flat_map(|(key, desc)| {
-                let key = Span::styled(format!(" {key} "), key_style);
-                let desc = Span::styled(format!(" {desc} "), val_style);
+                let key = Span::styled(format!(" {key} "), title_bar.key);
+                let desc = Span::styled(format!(" {desc} "), title_bar.value);
                 [key, desc]
             })
             .collect();
-        spans.push(Span::styled(
-            complete_perc,
-            Style::new().fg(theme::LIGHT_YELLOW).bg(theme::DARK_GRAY),
-        ));
-        Line::from(spans)
-            .centered()
-            .style((Color::Indexed(236), Color::Indexed(232)))
+        if self.tm_animation.paused() {
+            spans.push(Span::styled(" PAUSED ", title_bar.key));
+        }
+        match self.tm_animation.snoozes() {
+            0 => {}
+            1 => spans.push(Span::styled(" SNOOZED ", title_bar.key)),
+            n => spans.push(Span::styled(format!(" SNOOZED x{n} "), title_bar.key)),
+        }
+        spans.push(Span::styled(complete_perc, title_bar.progress));
+        Line::from(spans).centered().style(title_bar.bar)
     }
 
-    fn draw(&mut self, frame: &mut Frame) {
+    /// Draws the whole screen, `run` does it once per frame.
+    pub fn draw(&mut self, frame: &mut Frame) {
         let area = frame.area();
         let vertical = Layout::vertical([
             Constraint::Length(1),
@@ -173,19 +776,31 @@ impl App {
         ]);
         let horizontal = Layout::horizontal([Constraint::Percentage(30), Constraint::Min(0)]);
         let [title_bar, main_area, bottom_bar] = vertical.areas(area);
+        self.title_bar = title_bar;
 
+        let status = match &self.status {
+            Some(s) => s.clone(),
+            None => format!("{}x{}", area.width, area.height),
+        };
         let main_frame = Block::new()
-            .style(Style::new().bg(DARK_BLUE))
-            .title(Title::from(format!("{}x{}", area.width, area.height)));
+            .style(self.theme.root)
+            .title(Title::from(status));
         frame.render_widget(main_frame, area);
 
-        let message_fps = format!("{:.2} FPS", self.fps.fps());
+        let message_fps = format!(
+            "{:.2} FPS {:.1}ms",
+            self.fps.fps(),
+            self.fps.frame_time().as_secs_f64() * 1000.0
+        );
         let title_fps = Title::from(message_fps.to_span().dim())
             .alignment(layout::Alignment::Left)
             .position(ratatui::widgets::block::Position::Top);
-        let block_info = Block::bordered().title(title_fps).border_set(border::THICK);
+        let block_info = Block::bordered()
+            .title(title_fps)
+            .border_set(border::THICK)
+            .border_style(self.tm_animation.border_style());
         match self.state {
-            AppState::Main => {
+            AppState::Main | AppState::Prompt | AppState::Palette => {
                 frame.render_widget(&self.tm_animation, main_area);
                 frame.render_widget(block_info, main_area);
             }
@@ -195,67 +810,205 @@ impl App {
                 frame.render_widget(&self.tm_animation, animation_area);
                 frame.render_widget(block_info, animation_area);
             }
+            AppState::Presets => {
+                let [list_area, animation_area] = horizontal.areas(main_area);
+                self.render_presets(frame, list_area);
+                frame.render_widget(&self.tm_animation, animation_area);
+                frame.render_widget(block_info, animation_area);
+            }
             _ => {}
         }
-        frame.render_widget(render_bottom_bar(), bottom_bar);
+        if self.state == AppState::Prompt {
+            frame.render_widget(&self.prompt, bottom_bar);
+            frame.set_cursor_position(bottom_bar.offset(self.prompt.cursor_offset()));
+        } else {
+            let keys = self.keymap.help(self.context());
+            frame.render_widget(
+                render_bottom_bar(&self.theme.key_binding, &keys, &self.steps),
+                bottom_bar,
+            );
+        }
         frame.render_widget(self.get_tm_info_widget(), title_bar);
+        if self.tm_animation.flashing() {
+            frame.render_widget(Block::new().style(Style::new().bg(theme::WHITE)), area);
+        }
+        if self.show_perf {
+            // top right corner of the animation
+            let width = main_area.width.min(48);
+            let height = main_area.height.min(16);
+            let overlay = layout::Rect::new(main_area.right() - width, main_area.y, width, height);
+            frame.render_widget(fps::Overlay(&self.fps, &self.theme.popup), overlay);
+        }
+        if self.show_help {
+            frame.render_widget(
+                HelpOverlay(&self.keymap, &self.steps, &self.theme.popup),
+                main_area,
+            );
+        }
+        if self.show_logs {
+            frame.render_widget(LogOverlay(&self.log, &self.theme.popup), main_area);
+        }
+        if self.state == AppState::Palette {
+            let popup = Palette::popup(main_area);
+            frame.render_widget(&mut self.palette, popup);
+            frame.set_cursor_position(popup.offset(self.palette.cursor_offset()));
+        }
+        self.color_depth.apply(frame.buffer_mut());
+    }
+
+    fn render_presets(&mut self, frame: &mut Frame, area: layout::Rect) {
+        let style = &self.theme.player;
+        let block = Block::bordered()
+            .title(" Presets ")
+            .border_style(style.header);
+        self.preset_area = block.inner(area);
+        if self.presets.is_empty() {
+            let hint = Paragraph::new("No presets, add a [presets] table to the configuration")
+                .wrap(Wrap { trim: true })
+                .style(Style::new().fg(style.text))
+                .block(block);
+            frame.render_widget(hint, area);
+            return;
+        }
+        let items: Vec<ListItem> = self
+            .presets
+            .iter()
+            .map(|p| {
+                let shape = p.shape.as_deref().unwrap_or("random");
+                ListItem::new(format!(
+                    "{}  {} {shape}",
+                    p.name,
+                    cli::format_duration(&p.duration)
+                ))
+                .fg(style.text)
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .bg(style.row)
+            .highlight_style(style.selected)
+            .highlight_symbol(">");
+        frame.render_stateful_widget(list, area, &mut self.preset_list);
+    }
+}
+
+// index of the list row at `pos`, `area` being where the rows are drawn
+fn list_row(area: Rect, offset: usize, len: usize, pos: Position) -> Option<usize> {
+    if !area.contains(pos) {
+        return None;
     }
+    let i = offset + usize::from(pos.y - area.y);
+    (i < len).then_some(i)
 }
 
-fn render_bottom_bar() -> impl Widget + 'static {
-    let keys = [
-        ("h/←", "Sub 1s"),
-        ("l/→", "Add 1s"),
-        ("k/↑", "Add 1m"),
-        ("j/↓", "Sub 1m"),
-        ("r", "Reset time"),
-        ("q", "Quit"),
-    ];
-    let key_style = Style::new().fg(theme::BLACK).bg(theme::DARK_GRAY);
-    let desc_style = Style::new().fg(theme::DARK_GRAY).bg(theme::BLACK);
+fn random_shape(custom: &[shapes::Custom], color: Color) -> shapes::ShapeSelect {
+    let builtin = shapes::ShapeSelect::COUNT as usize;
+    let n = rand::thread_rng().gen_range(0..builtin + custom.len());
+    match n.checked_sub(builtin) {
+        Some(i) => shapes::ShapeSelect::CustomSelect(custom[i].clone()),
+        None => shapes::ShapeSelect::select_from(n as u32, color),
+    }
+}
+
+// the description of `action`, with the configured step for the timeout ones
+fn describe(action: Action, steps: &anime::Steps) -> String {
+    let (sign, step) = match action {
+        Action::AddSmall => ("Add", steps.small),
+        Action::SubSmall => ("Sub", steps.small),
+        Action::AddMedium => ("Add", steps.medium),
+        Action::SubMedium => ("Sub", steps.medium),
+        Action::AddLarge => ("Add", steps.large),
+        Action::SubLarge => ("Sub", steps.large),
+        Action::Snooze => ("Snooze", steps.snooze),
+        _ => return action.description().to_string(),
+    };
+    format!("{sign} {}", cli::format_duration(&step))
+}
+
+fn render_bottom_bar(
+    style: &theme::KeyBinding,
+    keys: &[(String, Action)],
+    steps: &anime::Steps,
+) -> impl Widget + 'static {
     let spans: Vec<Span> = keys
         .iter()
-        .flat_map(|(key, desc)| {
-            let key = Span::styled(format!(" {key} "), key_style);
-            let desc = Span::styled(format!(" {desc} "), desc_style);
+        .flat_map(|(key, action)| {
+            let key = Span::styled(format!(" {key} "), style.key);
+            let desc = Span::styled(format!(" {} ", describe(*action, steps)), style.description);
             [key, desc]
         })
         .collect();
-    Line::from(spans)
-        .centered()
-        .style((Color::Indexed(236), Color::Indexed(232)))
+    Line::from(spans).centered().style(style.bar)
+}
+
+/// Every key of the keymap, drawn centered over `area`.
+struct HelpOverlay<'a>(&'a keymap::Keymap, &'a anime::Steps, &'a theme::Popup);
+
+impl Widget for HelpOverlay<'_> {
+    fn render(self, area: layout::Rect, buf: &mut Buffer) {
+        let sections = [
+            ("Timer", keymap::Context::Main),
+            ("Lists", keymap::Context::List),
+            ("Anywhere", keymap::Context::Global),
+        ];
+        let mut lines = vec![];
+        for (title, context) in sections {
+            lines.push(Line::from(title).bold());
+            lines.extend(self.0.help(context).into_iter().map(|(keys, action)| {
+                Line::from(format!("  {keys:<16} {}", describe(action, self.1)))
+            }));
+            lines.push(Line::default());
+        }
+        let width = area.width.min(44);
+        let height = area.height.min(lines.len() as u16 + 2);
+        let popup = layout::Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + (area.height - height) / 2,
+            width,
+            height,
+        );
+        Clear.render(popup, buf);
+        Paragraph::new(lines)
+            .block(Block::bordered().title(" Keys, any key to close "))
+            .style(self.2.body)
+            .render(popup, buf);
+    }
 }
 
-// fn get_center_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
-//     let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
-//     let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
-//     let [area] = vertical.areas(area);
-//     let [area] = horizontal.areas(area);
-//     area
-// }
-
-fn timeout_complete() {
-    // let nb_sound_files = Asset::iter().count();
-    // let rand_select = rand::thread_rng().gen_range(0..nb_sound_files);
-    // let sounds: Vec<_> = Asset::iter().collect();
-    // let sound_select = sounds.get(rand_select).unwrap().clone();
-
-    // std::thread::spawn(move || {
-    //     let (_s, sh) = rodio::OutputStream::try_default().unwrap();
-    //     let file_data = Asset::get(sound_select.as_ref()).unwrap();
-
-    //     let cursor = std::io::Cursor::new(file_data.data);
-    //     let reader = std::io::BufReader::new(cursor);
-    //     let source = rodio::Decoder::new(reader).unwrap();
-    //     let sink = rodio::Sink::try_new(&sh).unwrap();
-
-    //     sink.append(source);
-    //     sink.sleep_until_end();
-    // });
+/// The last messages, drawn centered over `area`.
+struct LogOverlay<'a>(&'a [String], &'a theme::Popup);
+
+impl Widget for LogOverlay<'_> {
+    fn render(self, area: layout::Rect, buf: &mut Buffer) {
+        let width = area.width.min(64);
+        let height = area.height.min(16);
+        let popup = layout::Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + (area.height - height) / 2,
+            width,
+            height,
+        );
+        // the newest at the bottom
+        let shown = usize::from(height.saturating_sub(2));
+        let lines: Vec<Line> = if self.0.is_empty() {
+            vec![Line::from("Nothing logged yet").dim()]
+        } else {
+            let skip = self.0.len().saturating_sub(shown);
+            self.0[skip..]
+                .iter()
+                .map(|m| Line::from(m.as_str()))
+                .collect()
+        };
+        Clear.render(popup, buf);
+        Paragraph::new(lines)
+            .block(Block::bordered().title(" Logs, any key to close "))
+            .style(self.1.body)
+            .render(popup, buf);
+    }
 }
 
 pub fn get_time_left_formated(d: &Duration) -> String {
-    let m = d.as_secs() / 60;
+    let m = d.as_secs() / 60 % 60;
     let s = d.as_secs() % 60;
     let h = d.as_secs() / 3600;
     if h > 0 {
@@ -266,3 +1019,25 @@ pub fn get_time_left_formated(d: &Duration) -> String {
         format!("{}s", s)
     }
 }
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn when_hours_should_wrap_minutes() {
+        assert_eq!(
+            "1h 30m 0s",
+            get_time_left_formated(&Duration::from_secs(5400))
+        );
+        assert_eq!(
+            "2h 0m 5s",
+            get_time_left_formated(&Duration::from_secs(7205))
+        );
+        assert_eq!(
+            "59m 59s",
+            get_time_left_formated(&Duration::from_secs(3599))
+        );
+    }
+}
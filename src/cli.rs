@@ -1,12 +1,103 @@
-use std::{ops::Add, time::Duration};
+use std::{ops::Add, path::PathBuf, time::Duration};
 
 use clap::{Parser, Subcommand};
+use ratatui::style::Color;
+
+use crate::anime::{Adjust, Easing};
+use crate::clock::ClockKind;
+use crate::fps::FrameMode;
+use crate::marker::MarkerKind;
+use crate::shapes::Reveal;
+use crate::theme::ColorChoice;
 
 #[derive(Parser, Debug)]
 pub struct Cli {
+    /// Configuration file, TOML or JSON [default: $XDG_CONFIG_HOME/crb-alarm/config.toml]
+    #[arg(long, value_name = "PATH", global = true)]
+    pub config: Option<PathBuf>,
+
     #[arg(short, long, value_name = "FLOAT", default_value_t = 60.0)]
     pub frame_rate: f64,
 
+    /// Whether the frame rate drops when nothing moves or the terminal is unfocused
+    #[arg(long, value_enum, default_value_t = FrameMode::default())]
+    pub frame_mode: FrameMode,
+
+    /// Colour stops for the progress, e.g. `green,#ffb000,red`
+    #[arg(long, value_name = "COLORS", value_delimiter = ',')]
+    pub gradient: Vec<Color>,
+
+    /// Colour the arc/spiral segments by their position instead of the progress
+    #[arg(long)]
+    pub gradient_along_path: bool,
+
+    /// Seconds before the end where the urgency effects start, 0 to disable [default: 10]
+    #[arg(long, value_name = "SECS")]
+    pub urgency_secs: Option<u64>,
+
+    /// Disable pulsing, shaking and flashing effects
+    #[arg(long)]
+    pub reduced_motion: bool,
+
+    /// What the timeout keys change: the time left, the total or both [default: both]
+    #[arg(long, value_enum)]
+    pub adjust: Option<Adjust>,
+
+    /// Clock driving the countdown, `wall` keeps counting while the machine sleeps
+    #[arg(long, value_enum, default_value_t = ClockKind::default())]
+    pub clock: ClockKind,
+
+    /// Leave the mouse to the terminal, e.g. to select text
+    #[arg(long)]
+    pub no_mouse: bool,
+
+    /// Unix socket taking commands, one per line: an action name such as
+    /// `pause`, or `set 25m`
+    #[arg(long, value_name = "PATH")]
+    pub ipc: Option<PathBuf>,
+
+    /// Easing used by the animation when the timeout changes
+    #[arg(long, value_enum, default_value_t = Easing::default())]
+    pub easing: Easing,
+
+    /// Built-in theme (dark, light, solarized, high-contrast) or a TOML theme file,
+    /// a file is reloaded when it changes
+    /// [default: dark]
+    #[arg(long, value_name = "NAME|PATH")]
+    pub theme: Option<String>,
+
+    /// Colours to use, truecolor values are mapped to the nearest palette entry [default: auto]
+    #[arg(long, value_enum)]
+    pub color: Option<ColorChoice>,
+
+    /// Shape to animate, e.g. arc, spiral, rain or a custom shape [default: random]
+    #[arg(long, value_name = "NAME")]
+    pub shape: Option<String>,
+
+    /// Sound played at the end: random, none or one of the embedded sounds [default: random]
+    #[arg(long, value_name = "NAME")]
+    pub sound: Option<String>,
+
+    /// Glyphs to draw the shapes with [default: picked from the terminal]
+    #[arg(long, value_enum)]
+    pub marker: Option<MarkerKind>,
+
+    /// TOML file of custom shapes [default: $XDG_CONFIG_HOME/crb-alarm/shapes.toml]
+    #[arg(long, value_name = "PATH")]
+    pub shapes: Option<PathBuf>,
+
+    /// PNG, PBM or PGM image revealed as the time goes
+    #[arg(long, value_name = "PATH")]
+    pub image: Option<PathBuf>,
+
+    /// Order in which the image pixels show up
+    #[arg(long, value_enum, default_value_t = Reveal::default())]
+    pub reveal: Reveal,
+
+    /// Use the light pixels of the image instead of the dark ones
+    #[arg(long)]
+    pub image_invert: bool,
+
     #[command(subcommand)]
     pub cmd: Option<Commands>,
 }
@@ -14,6 +105,26 @@ pub struct Cli {
 #[derive(Subcommand, Debug, Clone)]
 pub enum Commands {
     Timeout(DurationTmArg),
+    /// Draw a shape with every marker to see which one suits the terminal font
+    Markers,
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        cmd: ConfigCmd,
+    },
+    /// A preset of the configuration, `crb-alarm tea`
+    #[command(external_subcommand)]
+    Preset(Vec<String>),
+}
+
+#[derive(Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigCmd {
+    /// Print the configuration in effect, after the file, environment and flags
+    Show,
+    /// Print where the configuration file is looked up
+    Path,
+    /// Check the configuration file and exit with an error if it is invalid
+    Validate,
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -22,38 +133,73 @@ pub struct DurationTmArg {
 }
 //TODO: move this
 //TODO: have HMS display
+
+/// `$XDG_CONFIG_HOME/crb-alarm`, falling back on `~/.config/crb-alarm`.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(base.join("crb-alarm"))
+}
+
 impl DurationTmArg {
-    //TODO: this need love... improve code here.. low prio thought
+    pub fn as_str(&self) -> &str {
+        &self.duration
+    }
+
     pub fn parse(&self) -> Option<Duration> {
-        let parsable = self.duration.replace('h', "h ");
-        let parsable = parsable.replace('s', "s ");
-        let parsable = parsable.replace('m', "m ");
-        let parsable: Vec<&str> = parsable.split_whitespace().collect();
+        parse_duration(&self.duration)
+    }
+}
 
-        if parsable.is_empty() {
+/// Parses durations such as `1h30m`, `90s` or `4m 30s`.
+//TODO: this need love... improve code here.. low prio thought
+pub fn parse_duration(duration: &str) -> Option<Duration> {
+    let parsable = duration.replace('h', "h ");
+    let parsable = parsable.replace('s', "s ");
+    let parsable = parsable.replace('m', "m ");
+    let parsable: Vec<&str> = parsable.split_whitespace().collect();
+
+    if parsable.is_empty() {
+        return None;
+    }
+
+    let mut d: Duration = Duration::new(0, 0);
+    for s in parsable {
+        if s.ends_with('s') {
+            let n = s.strip_suffix('s')?;
+            let n: u64 = n.parse().ok()?;
+            d = d.add(Duration::from_secs(n));
+        } else if s.ends_with('m') {
+            let n = s.strip_suffix('m')?;
+            let n: u64 = n.parse().ok()?;
+            d = d.add(Duration::from_secs(n * 60));
+        } else if s.ends_with('h') {
+            let n = s.strip_suffix('h')?;
+            let n: u64 = n.parse().ok()?;
+            d = d.add(Duration::from_secs(n * 60 * 60));
+        } else {
             return None;
         }
+    }
 
-        let mut d: Duration = Duration::new(0, 0);
-        for s in parsable {
-            if s.ends_with('s') {
-                let n = s.strip_suffix('s')?;
-                let n: u64 = n.parse().ok()?;
-                d = d.add(Duration::from_secs(n));
-            } else if s.ends_with('m') {
-                let n = s.strip_suffix('m')?;
-                let n: u64 = n.parse().ok()?;
-                d = d.add(Duration::from_secs(n * 60));
-            } else if s.ends_with('h') {
-                let n = s.strip_suffix('h')?;
-                let n: u64 = n.parse().ok()?;
-                d = d.add(Duration::from_secs(n * 60 * 60));
-            } else {
-                return None;
-            }
-        }
+    Some(d)
+}
 
-        Some(d)
+/// Writes `d` the way [`parse_duration`] reads it, e.g. `1h30m`, zero units left out.
+pub fn format_duration(d: &Duration) -> String {
+    let secs = d.as_secs();
+    let units = [(secs / 3600, 'h'), (secs / 60 % 60, 'm'), (secs % 60, 's')];
+    let s: String = units
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, u)| format!("{n}{u}"))
+        .collect();
+    if s.is_empty() {
+        "0s".to_string()
+    } else {
+        s
     }
 }
 
@@ -136,4 +282,19 @@ mod test {
         };
         assert_eq!(Duration::from_secs(6), d.parse().unwrap());
     }
+
+    #[test]
+    fn when_formatting_should_parse_back() {
+        for (secs, s) in [
+            (0, "0s"),
+            (45, "45s"),
+            (600, "10m"),
+            (5400, "1h30m"),
+            (3601, "1h1s"),
+        ] {
+            let d = Duration::from_secs(secs);
+            assert_eq!(s, format_duration(&d));
+            assert_eq!(Some(d), parse_duration(s));
+        }
+    }
 }
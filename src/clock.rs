@@ -0,0 +1,113 @@
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Tells the time to the countdown, the frame pacing and the fps counter.
+pub trait Clock: Debug + Send + Sync {
+    /// Time since an arbitrary start, only differences between two readings matter.
+    fn now(&self) -> Duration;
+}
+
+/// A clock shared by everything timed in the app.
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Monotonic time of the system, never goes back. On Linux it doesn't count
+/// the time the machine was asleep.
+#[derive(Debug)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// Time of day of the system, counts the time the machine was asleep but
+/// follows the changes of the system time.
+#[derive(Debug, Default)]
+pub struct WallClock;
+
+impl Clock for WallClock {
+    fn now(&self) -> Duration {
+        // before 1970 only when the system time is badly off
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+}
+
+/// Moves only when told to, clones share the same time. For tests.
+#[derive(Debug, Default, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl ManualClock {
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now = now.saturating_add(by);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        *self.now.lock().unwrap()
+    }
+}
+
+/// Clock picked on the command line.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ClockKind {
+    /// steady, stops while the machine sleeps
+    #[default]
+    Monotonic,
+    /// time of day, an alarm still rings on time after a suspend
+    Wall,
+}
+
+impl ClockKind {
+    pub fn clock(self) -> SharedClock {
+        match self {
+            ClockKind::Monotonic => Arc::new(SystemClock::default()),
+            ClockKind::Wall => Arc::new(WallClock),
+        }
+    }
+}
+
+/// A system monotonic clock, the default of everything timed.
+pub fn system() -> SharedClock {
+    ClockKind::default().clock()
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn when_advanced_should_move_every_clone() {
+        let manual = ManualClock::default();
+        let shared: SharedClock = Arc::new(manual.clone());
+        assert_eq!(Duration::ZERO, shared.now());
+        manual.advance(Duration::from_secs(90));
+        manual.advance(Duration::from_millis(500));
+        assert_eq!(Duration::from_millis(90_500), shared.now());
+
+        let system = system();
+        let before = system.now();
+        assert!(system.now() >= before);
+        assert!(WallClock.now() > Duration::from_secs(1_600_000_000));
+    }
+}
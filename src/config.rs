@@ -0,0 +1,782 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use clap::ValueEnum;
+use color_eyre::{eyre::eyre, Result};
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::anime::{Adjust, Steps, Urgency};
+use crate::cli::{self, Cli, Commands, ConfigCmd};
+use crate::keymap::Keymap;
+use crate::shapes::{Custom, ShapeSelect};
+use crate::sound::Sound;
+use crate::spoty;
+use crate::theme::{ColorChoice, Theme};
+
+/// Prefix of the variables overriding the file, e.g. `CRB_ALARM_DURATION=25m`.
+pub const ENV_PREFIX: &str = "CRB_ALARM_";
+
+const DEFAULT_DURATION: &str = "5s";
+const DEFAULT_THEME: &str = "dark";
+
+/// User settings, read from `$XDG_CONFIG_HOME/crb-alarm/config.toml` (or `.json`):
+///
+/// ```toml
+/// duration = "25m"
+/// shape = "spiral"
+/// theme = "solarized"
+/// color = "256"
+/// gradient = ["green", "#ffb000", "red"]
+/// sound = "PantsTime"
+/// adjust = "remaining"
+/// reduced-motion = false
+///
+/// [steps]
+/// large = "5m"
+/// snooze = "10m"
+///
+/// [urgency]
+/// threshold = "30s"
+/// shake = false
+///
+/// [keys]
+/// preset = "emacs"
+/// quit = "q Ctrl+c"
+///
+/// [spotify]
+/// client-id = "..."
+///
+/// [presets]
+/// tea = { duration = "4m", shape = "arc", sound = "PantsTime" }
+/// ```
+///
+/// Every layer only holds what it sets, later layers win: the file, then the
+/// `CRB_ALARM_*` variables, then the command line flags.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// countdown when none is given on the command line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<String>,
+    /// shape name, random when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shape: Option<String>,
+    /// custom shapes file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shapes: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<ColorChoice>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub gradient: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sound: Option<String>,
+    /// what the timeout keys change
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adjust: Option<Adjust>,
+    /// time added or removed by the timeout keys
+    pub steps: StepsDef,
+    /// no pulsing, shaking or flashing, whatever `[urgency]` says
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reduced_motion: Option<bool>,
+    /// effects of the last seconds
+    pub urgency: UrgencyDef,
+    /// action name to keys, see [`Keymap::from_config`]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub keys: BTreeMap<String, String>,
+    pub spotify: spoty::Settings,
+    /// named timers, started with `crb-alarm <name>`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub presets: BTreeMap<String, PresetDef>,
+}
+
+/// A `[presets]` entry, unset values come from the rest of the configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PresetDef {
+    pub duration: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shape: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sound: Option<String>,
+}
+
+/// The `[steps]` table, unset steps keep their default.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StepsDef {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub small: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub medium: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub large: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snooze: Option<String>,
+}
+
+/// The `[urgency]` table, unset values keep their default.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct UrgencyDef {
+    /// time left when the effects start, `0s` disables them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pulse: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blink_border: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shake: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flash: Option<bool>,
+}
+
+/// A timer ready to start.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preset {
+    /// empty for the timer of the command line
+    pub name: String,
+    pub duration: Duration,
+    /// `None` for a random shape
+    pub shape: Option<String>,
+    pub sound: Sound,
+}
+
+impl Preset {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            name: String::new(),
+            duration,
+            shape: None,
+            sound: Sound::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Parses a TOML file, or JSON when `json` is set.
+    pub fn parse(src: &str, json: bool) -> Result<Self> {
+        if json {
+            Ok(serde_json::from_str(src)?)
+        } else {
+            Ok(toml::from_str(src)?)
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let src = std::fs::read_to_string(path).map_err(|e| eyre!("{}: {e}", path.display()))?;
+        let json = path.extension().is_some_and(|e| e == "json");
+        Self::parse(&src, json).map_err(|e| eyre!("{}: {e}", path.display()))
+    }
+
+    /// Settings from the `CRB_ALARM_*` variables, and the rspotify ones.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let get = |name: &str| var(&format!("{ENV_PREFIX}{name}")).filter(|v| !v.is_empty());
+        let color = get("COLOR")
+            .map(|c| ColorChoice::from_str(&c, true).map_err(|e| eyre!("{ENV_PREFIX}COLOR: {e}")))
+            .transpose()?;
+        let adjust = get("ADJUST")
+            .map(|a| Adjust::from_str(&a, true).map_err(|e| eyre!("{ENV_PREFIX}ADJUST: {e}")))
+            .transpose()?;
+        let reduced_motion = get("REDUCED_MOTION")
+            .map(|r| {
+                r.parse()
+                    .map_err(|e| eyre!("{ENV_PREFIX}REDUCED_MOTION: {e}"))
+            })
+            .transpose()?;
+        Ok(Self {
+            duration: get("DURATION"),
+            shape: get("SHAPE"),
+            shapes: get("SHAPES").map(PathBuf::from),
+            theme: get("THEME"),
+            color,
+            gradient: get("GRADIENT")
+                .map(|g| g.split(',').map(|c| c.trim().to_string()).collect())
+                .unwrap_or_default(),
+            sound: get("SOUND"),
+            adjust,
+            steps: StepsDef::default(),
+            reduced_motion,
+            urgency: UrgencyDef::default(),
+            keys: BTreeMap::new(),
+            spotify: spoty::Settings {
+                client_id: var("RSPOTIFY_CLIENT_ID").filter(|v| !v.is_empty()),
+                redirect_uri: var("RSPOTIFY_REDIRECT_URI").filter(|v| !v.is_empty()),
+                token_cache: None,
+            },
+            presets: BTreeMap::new(),
+        })
+    }
+
+    /// Settings given as flags.
+    pub fn from_cli(args: &Cli) -> Self {
+        let duration = match &args.cmd {
+            Some(Commands::Timeout(t)) => Some(t.as_str().to_string()),
+            _ => None,
+        };
+        Self {
+            duration,
+            shape: args.shape.clone(),
+            shapes: args.shapes.clone(),
+            theme: args.theme.clone(),
+            color: args.color,
+            gradient: args.gradient.iter().map(Color::to_string).collect(),
+            sound: args.sound.clone(),
+            adjust: args.adjust,
+            reduced_motion: args.reduced_motion.then_some(true),
+            urgency: UrgencyDef {
+                threshold: args.urgency_secs.map(|s| format!("{s}s")),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// `over` on top of `self`.
+    pub fn merge(self, over: Config) -> Config {
+        let mut keys = self.keys;
+        keys.extend(over.keys);
+        let mut presets = self.presets;
+        presets.extend(over.presets);
+        Config {
+            duration: over.duration.or(self.duration),
+            shape: over.shape.or(self.shape),
+            shapes: over.shapes.or(self.shapes),
+            theme: over.theme.or(self.theme),
+            color: over.color.or(self.color),
+            gradient: if over.gradient.is_empty() {
+                self.gradient
+            } else {
+                over.gradient
+            },
+            sound: over.sound.or(self.sound),
+            adjust: over.adjust.or(self.adjust),
+            steps: StepsDef {
+                small: over.steps.small.or(self.steps.small),
+                medium: over.steps.medium.or(self.steps.medium),
+                large: over.steps.large.or(self.steps.large),
+                snooze: over.steps.snooze.or(self.steps.snooze),
+            },
+            reduced_motion: over.reduced_motion.or(self.reduced_motion),
+            urgency: UrgencyDef {
+                threshold: over.urgency.threshold.or(self.urgency.threshold),
+                pulse: over.urgency.pulse.or(self.urgency.pulse),
+                blink_border: over.urgency.blink_border.or(self.urgency.blink_border),
+                shake: over.urgency.shake.or(self.urgency.shake),
+                flash: over.urgency.flash.or(self.urgency.flash),
+            },
+            keys,
+            spotify: spoty::Settings {
+                client_id: over.spotify.client_id.or(self.spotify.client_id),
+                redirect_uri: over.spotify.redirect_uri.or(self.spotify.redirect_uri),
+                token_cache: over.spotify.token_cache.or(self.spotify.token_cache),
+            },
+            presets,
+        }
+    }
+
+    // the values of the preset `name` as a layer
+    fn preset_layer(&self, name: &str) -> Result<Config> {
+        let def = self.presets.get(name).ok_or_else(|| {
+            let names: Vec<&str> = self.presets.keys().map(String::as_str).collect();
+            eyre!(
+                "unknown command or preset `{name}`, presets: {}",
+                if names.is_empty() {
+                    "none configured".to_string()
+                } else {
+                    names.join(", ")
+                }
+            )
+        })?;
+        Ok(Config {
+            duration: Some(def.duration.clone()),
+            shape: def.shape.clone(),
+            sound: def.sound.clone(),
+            ..Default::default()
+        })
+    }
+
+    /// Every layer merged, for the command line `args`.
+    pub fn resolve(args: &Cli) -> Result<Self> {
+        let file = match path(args) {
+            Some(p) if p.exists() || explicit_path(args).is_some() => Self::load(&p)?,
+            _ => Self::default(),
+        };
+        let env = Self::from_vars(|k| std::env::var(k).ok())?;
+        let mut config = file.merge(env);
+        // flags still win over the preset
+        if let Some(name) = preset_name(args)? {
+            let layer = config.preset_layer(name)?;
+            config = config.merge(layer);
+        }
+        Ok(config.merge(Self::from_cli(args)))
+    }
+
+    /// The timer to start, named after the preset it comes from.
+    pub fn timer(&self, name: &str) -> Result<Preset> {
+        Ok(Preset {
+            name: name.to_string(),
+            duration: self.duration()?,
+            shape: self.shape.clone().filter(|s| s != "random"),
+            sound: self.sound()?,
+        })
+    }
+
+    /// Every `[presets]` entry, sorted by name.
+    pub fn presets(&self) -> Result<Vec<Preset>> {
+        self.presets
+            .keys()
+            .map(|name| {
+                self.clone()
+                    .merge(self.preset_layer(name)?)
+                    .timer(name)
+                    .map_err(|e| eyre!("preset `{name}`: {e}"))
+            })
+            .collect()
+    }
+
+    pub fn duration(&self) -> Result<Duration> {
+        let d = self.duration.as_deref().unwrap_or(DEFAULT_DURATION);
+        cli::parse_duration(d).ok_or_else(|| eyre!("invalid duration `{d}`, e.g. 1h30m or 90s"))
+    }
+
+    pub fn adjust(&self) -> Adjust {
+        self.adjust.unwrap_or_default()
+    }
+
+    pub fn steps(&self) -> Result<Steps> {
+        let step = |value: &Option<String>, default: Duration, name: &str| match value {
+            None => Ok(default),
+            Some(v) => cli::parse_duration(v)
+                .filter(|d| !d.is_zero())
+                .ok_or_else(|| eyre!("invalid {name} step `{v}`, e.g. 10m or 30s")),
+        };
+        let default = Steps::default();
+        Ok(Steps {
+            small: step(&self.steps.small, default.small, "small")?,
+            medium: step(&self.steps.medium, default.medium, "medium")?,
+            large: step(&self.steps.large, default.large, "large")?,
+            snooze: step(&self.steps.snooze, default.snooze, "snooze")?,
+        })
+    }
+
+    /// The effects of the last seconds, `None` when disabled or with reduced motion.
+    pub fn urgency(&self) -> Result<Option<Urgency>> {
+        let default = Urgency::default();
+        let threshold = match &self.urgency.threshold {
+            None => default.threshold,
+            Some(t) => cli::parse_duration(t)
+                .ok_or_else(|| eyre!("invalid urgency threshold `{t}`, e.g. 30s or 0s"))?,
+        };
+        if self.reduced_motion.unwrap_or(false) || threshold.is_zero() {
+            return Ok(None);
+        }
+        let u = &self.urgency;
+        Ok(Some(Urgency {
+            threshold,
+            pulse: u.pulse.unwrap_or(default.pulse),
+            blink_border: u.blink_border.unwrap_or(default.blink_border),
+            shake: u.shake.unwrap_or(default.shake),
+            flash: u.flash.unwrap_or(default.flash),
+        }))
+    }
+
+    pub fn theme_name(&self) -> &str {
+        self.theme.as_deref().unwrap_or(DEFAULT_THEME)
+    }
+
+    /// The built-in theme, or the theme file.
+    pub fn theme(&self) -> Result<Theme> {
+        let name = self.theme_name();
+        match Theme::builtin(name) {
+            Some(theme) => Ok(theme),
+            None => Theme::load(Path::new(name)),
+        }
+    }
+
+    pub fn color(&self) -> ColorChoice {
+        self.color.unwrap_or_default()
+    }
+
+    pub fn gradient(&self) -> Result<Vec<Color>> {
+        self.gradient
+            .iter()
+            .map(|c| Color::from_str(c).map_err(|_| eyre!("invalid gradient colour `{c}`")))
+            .collect()
+    }
+
+    pub fn sound(&self) -> Result<Sound> {
+        self.sound
+            .as_deref()
+            .map_or(Ok(Sound::default()), str::parse)
+    }
+
+    /// The custom shapes file, or the default one when it exists.
+    pub fn shapes_path(&self) -> Option<PathBuf> {
+        self.shapes.clone().or_else(|| {
+            cli::config_dir()
+                .map(|d| d.join("shapes.toml"))
+                .filter(|p| p.exists())
+        })
+    }
+
+    /// The configured shape among the built-in and `custom` ones, `None` for random.
+    pub fn shape(&self, custom: &[Custom], color: Color) -> Result<Option<ShapeSelect>> {
+        let Some(name) = &self.shape else {
+            return Ok(None);
+        };
+        if name == "random" {
+            return Ok(None);
+        }
+        ShapeSelect::find(name, custom, color)
+            .map(Some)
+            .ok_or_else(|| eyre!("unknown shape `{name}`"))
+    }
+
+    pub fn keymap(&self) -> Result<Keymap> {
+        Keymap::from_config(&self.keys)
+    }
+
+    /// Checks every value can be used.
+    pub fn validate(&self) -> Result<()> {
+        self.duration()?;
+        self.steps()?;
+        self.urgency()?;
+        self.keymap()?;
+        let theme = self.theme()?;
+        self.gradient()?;
+        self.sound()?;
+        let custom = match self.shapes_path() {
+            Some(path) => Custom::load_all(&path, theme.canvas.fg)?,
+            None => vec![],
+        };
+        self.shape(&custom, theme.canvas.fg)?;
+        for name in self.presets.keys() {
+            let preset = self.clone().merge(self.preset_layer(name)?);
+            preset
+                .shape(&custom, theme.canvas.fg)
+                .and_then(|_| preset.sound())
+                .and_then(|_| preset.duration())
+                .map_err(|e| eyre!("preset `{name}`: {e}"))?;
+        }
+        Ok(())
+    }
+
+    /// Copy with the defaults of the unset values filled in.
+    pub fn effective(&self) -> Self {
+        let mut c = self.clone();
+        c.duration
+            .get_or_insert_with(|| DEFAULT_DURATION.to_string());
+        c.shape.get_or_insert_with(|| "random".to_string());
+        c.theme.get_or_insert_with(|| DEFAULT_THEME.to_string());
+        c.color.get_or_insert_with(ColorChoice::default);
+        c.sound.get_or_insert_with(|| Sound::default().to_string());
+        c.adjust.get_or_insert_with(Adjust::default);
+        let steps = Steps::default();
+        for (value, default) in [
+            (&mut c.steps.small, steps.small),
+            (&mut c.steps.medium, steps.medium),
+            (&mut c.steps.large, steps.large),
+            (&mut c.steps.snooze, steps.snooze),
+        ] {
+            value.get_or_insert_with(|| cli::format_duration(&default));
+        }
+        c.reduced_motion.get_or_insert(false);
+        let urgency = Urgency::default();
+        c.urgency
+            .threshold
+            .get_or_insert_with(|| cli::format_duration(&urgency.threshold));
+        c.urgency.pulse.get_or_insert(urgency.pulse);
+        c.urgency.blink_border.get_or_insert(urgency.blink_border);
+        c.urgency.shake.get_or_insert(urgency.shake);
+        c.urgency.flash.get_or_insert(urgency.flash);
+        c
+    }
+}
+
+/// Name of the preset given as command, `crb-alarm tea`.
+pub fn preset_name(args: &Cli) -> Result<Option<&str>> {
+    match &args.cmd {
+        Some(Commands::Preset(words)) => match words.as_slice() {
+            [name] => Ok(Some(name.as_str())),
+            [name, rest @ ..] => Err(eyre!(
+                "preset `{name}` takes no argument, got `{}`, put flags before the name",
+                rest.join(" ")
+            )),
+            [] => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
+// `--config` or `CRB_ALARM_CONFIG`, which must exist
+fn explicit_path(args: &Cli) -> Option<PathBuf> {
+    args.config.clone().or_else(|| {
+        std::env::var_os(format!("{ENV_PREFIX}CONFIG"))
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    })
+}
+
+/// Where the configuration file is read from, `config.json` is only used
+/// when there is no `config.toml`.
+pub fn path(args: &Cli) -> Option<PathBuf> {
+    explicit_path(args).or_else(|| {
+        let dir = cli::config_dir()?;
+        let json = dir.join("config.json");
+        let toml = dir.join("config.toml");
+        Some(if !toml.exists() && json.exists() {
+            json
+        } else {
+            toml
+        })
+    })
+}
+
+/// `crb-alarm config ...`
+pub fn run(cmd: ConfigCmd, args: &Cli) -> Result<()> {
+    match cmd {
+        ConfigCmd::Path => match path(args) {
+            Some(p) => println!("{}", p.display()),
+            None => return Err(eyre!("no configuration directory, set XDG_CONFIG_HOME")),
+        },
+        ConfigCmd::Show => {
+            let config = Config::resolve(args)?.effective();
+            print!("{}", toml::to_string(&config)?);
+        }
+        ConfigCmd::Validate => {
+            Config::resolve(args)?.validate()?;
+            match path(args).filter(|p| p.exists()) {
+                Some(p) => println!("{}: ok", p.display()),
+                None => println!("no configuration file, defaults are ok"),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+
+    use clap::Parser;
+
+    use super::*;
+
+    fn vars(vars: &[(&str, &str)]) -> Config {
+        Config::from_vars(|k| {
+            vars.iter()
+                .find(|(name, _)| *name == k)
+                .map(|(_, v)| v.to_string())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn when_parsing_toml_or_json_should_read_the_same() {
+        let toml = Config::parse(
+            r#"
+            duration = "25m"
+            shape = "spiral"
+            color = "256"
+            gradient = ["green", "red"]
+            [keys]
+            quit = "q"
+            [spotify]
+            client-id = "abc"
+            "#,
+            false,
+        )
+        .unwrap();
+        let json = Config::parse(
+            r#"{"duration": "25m", "shape": "spiral", "color": "256",
+                "gradient": ["green", "red"], "keys": {"quit": "q"},
+                "spotify": {"client-id": "abc"}}"#,
+            true,
+        )
+        .unwrap();
+        assert_eq!(toml, json);
+        assert_eq!(Duration::from_secs(25 * 60), toml.duration().unwrap());
+        assert_eq!(ColorChoice::Ansi256, toml.color());
+        assert_eq!(vec![Color::Green, Color::Red], toml.gradient().unwrap());
+        assert_eq!(Some("abc".to_string()), toml.spotify.client_id);
+        assert!(Config::parse("volume = 3", false).is_err());
+    }
+
+    #[test]
+    fn when_merging_should_prefer_env_then_cli() {
+        let file = Config::parse(
+            "duration = \"25m\"\ntheme = \"light\"\nsound = \"none\"\n[keys]\nquit = \"q\"",
+            false,
+        )
+        .unwrap();
+        let env = vars(&[
+            ("CRB_ALARM_THEME", "solarized"),
+            ("CRB_ALARM_COLOR", "16"),
+            ("CRB_ALARM_SHAPE", ""),
+            ("RSPOTIFY_CLIENT_ID", "id"),
+        ]);
+        let args = Cli::parse_from(["crb-alarm", "--color", "never", "timeout", "1m"]);
+        let c = file.merge(env).merge(Config::from_cli(&args));
+        assert_eq!(Duration::from_secs(60), c.duration().unwrap());
+        assert_eq!("solarized", c.theme_name());
+        assert_eq!(ColorChoice::Never, c.color());
+        assert_eq!(Sound::Off, c.sound().unwrap());
+        assert_eq!(None, c.shape);
+        assert_eq!(Some("q"), c.keys.get("quit").map(String::as_str));
+        assert_eq!(Some("id".to_string()), c.spotify.client_id);
+    }
+
+    #[test]
+    fn when_steps_set_should_merge_per_step() {
+        let file = Config::parse("adjust = \"total\"\n[steps]\nlarge = \"5m\"", false).unwrap();
+        let over = Config::parse("[steps]\nsmall = \"10s\"", false).unwrap();
+        let env = vars(&[("CRB_ALARM_ADJUST", "remaining")]);
+        let c = file.merge(over).merge(env);
+        let steps = c.steps().unwrap();
+        assert_eq!(Duration::from_secs(10), steps.small);
+        assert_eq!(Duration::from_secs(60), steps.medium);
+        assert_eq!(Duration::from_secs(300), steps.large);
+        assert_eq!(Duration::from_secs(300), steps.snooze);
+        assert_eq!(Adjust::Remaining, c.adjust());
+        let args = Cli::parse_from(["crb-alarm", "--adjust", "both"]);
+        assert_eq!(Adjust::Both, c.merge(Config::from_cli(&args)).adjust());
+    }
+
+    #[test]
+    fn when_urgency_set_should_merge_per_effect() {
+        let file = Config::parse("[urgency]\nthreshold = \"30s\"\nshake = false", false).unwrap();
+        let over = Config::parse("[urgency]\nflash = false", false).unwrap();
+        let c = file.merge(over);
+        let urgency = c.urgency().unwrap().unwrap();
+        assert_eq!(Duration::from_secs(30), urgency.threshold);
+        assert!(urgency.pulse && urgency.blink_border);
+        assert!(!urgency.shake && !urgency.flash);
+
+        let args = Cli::parse_from(["crb-alarm", "--urgency-secs", "5"]);
+        let flags = c.clone().merge(Config::from_cli(&args));
+        let urgency = flags.urgency().unwrap().unwrap();
+        assert_eq!(Duration::from_secs(5), urgency.threshold);
+        assert!(!urgency.shake);
+
+        let env = vars(&[("CRB_ALARM_REDUCED_MOTION", "true")]);
+        assert_eq!(None, c.clone().merge(env).urgency().unwrap());
+        let args = Cli::parse_from(["crb-alarm", "--reduced-motion"]);
+        assert_eq!(
+            None,
+            c.clone().merge(Config::from_cli(&args)).urgency().unwrap()
+        );
+        let args = Cli::parse_from(["crb-alarm", "--urgency-secs", "0"]);
+        assert_eq!(None, c.merge(Config::from_cli(&args)).urgency().unwrap());
+        assert_eq!(
+            Some(Urgency::default()),
+            Config::default().urgency().unwrap()
+        );
+    }
+
+    #[test]
+    fn when_preset_should_override_file_but_not_flags() {
+        let file = Config::parse(
+            r#"
+            sound = "none"
+            [presets]
+            tea = { duration = "4m", shape = "spiral", sound = "PantsTime" }
+            egg = { duration = "7m" }
+            "#,
+            false,
+        )
+        .unwrap();
+        let args = Cli::parse_from(["crb-alarm", "--shape", "rain", "tea"]);
+        let name = preset_name(&args).unwrap().unwrap();
+        let layer = file.preset_layer(name).unwrap();
+        let c = file.clone().merge(layer).merge(Config::from_cli(&args));
+        let timer = c.timer(name).unwrap();
+        assert_eq!("tea", timer.name);
+        assert_eq!(Duration::from_secs(4 * 60), timer.duration);
+        assert_eq!(Some("rain".to_string()), timer.shape);
+        assert_eq!(Sound::Named("PantsTime".to_string()), timer.sound);
+
+        let presets = file.presets().unwrap();
+        assert_eq!(
+            vec!["egg", "tea"],
+            presets.iter().map(|p| p.name.as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!(Sound::Off, presets[0].sound);
+        assert_eq!(None, presets[0].shape);
+
+        let err = file.preset_layer("coffee").unwrap_err().to_string();
+        assert!(err.contains("presets: egg, tea"), "{err}");
+        let args = Cli::parse_from(["crb-alarm", "tea", "--shape", "rain"]);
+        assert!(preset_name(&args).is_err());
+    }
+
+    // an empty shapes file, rather than the one of the user running the tests,
+    // removed when dropped
+    struct NoShapes(PathBuf);
+
+    impl NoShapes {
+        fn new() -> Self {
+            static NEXT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+            let n = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let name = format!("crb-alarm-shapes-{}-{n}.toml", std::process::id());
+            let path = std::env::temp_dir().join(name);
+            std::fs::write(&path, "").unwrap();
+            Self(path)
+        }
+
+        fn isolate(&self, c: Config) -> Config {
+            Config {
+                shapes: Some(self.0.clone()),
+                ..c
+            }
+        }
+    }
+
+    impl Drop for NoShapes {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn when_unset_should_use_defaults() {
+        let c = Config::default();
+        assert_eq!(Duration::from_secs(5), c.duration().unwrap());
+        assert_eq!("dark", c.theme_name());
+        assert_eq!(Adjust::Both, c.adjust());
+        assert_eq!(Steps::default(), c.steps().unwrap());
+        assert!(NoShapes::new().isolate(c.clone()).validate().is_ok());
+        let shown = toml::to_string(&c.effective()).unwrap();
+        assert_eq!(c.effective(), Config::parse(&shown, false).unwrap());
+    }
+
+    #[test]
+    fn when_invalid_should_fail_validation() {
+        let no_shapes = NoShapes::new();
+        for src in [
+            "duration = \"soon\"",
+            "theme = \"/does/not/exist.toml\"",
+            "gradient = [\"not a colour\"]",
+            "sound = \"Pants\"",
+            "[keys]\nquit = \"Hyper+q\"",
+            "shape = \"blob\"",
+            "[steps]\nlarge = \"0s\"",
+            "[steps]\nsmall = \"1x\"",
+            "[urgency]\nthreshold = \"soon\"",
+        ] {
+            let c = no_shapes.isolate(Config::parse(src, false).unwrap());
+            assert!(c.validate().is_err(), "{src}");
+        }
+        assert!(Config::from_vars(|k| (k == "CRB_ALARM_COLOR").then(|| "8".to_string())).is_err());
+        assert!(Config::parse("adjust = \"elapsed\"", false).is_err());
+        assert!(Config::parse("[urgency]\nwobble = true", false).is_err());
+        let arc = Config::parse("shape = \"arc\"", false).unwrap();
+        assert!(no_shapes.isolate(arc).validate().is_ok());
+    }
+}
@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use ratatui::crossterm::event::{Event, EventStream};
+use tokio::sync::mpsc;
+
+use crate::ipc::IpcCommand;
+use crate::music_player::SpotifyResult;
+
+/// Everything the app reacts to, handled one at a time by `App::update`.
+#[derive(Debug)]
+pub enum AppEvent {
+    /// key, mouse, focus or resize from the terminal
+    Input(Event),
+    /// the next frame is due
+    Tick,
+    /// the completion sound ended, or why it couldn't play
+    Audio(Result<(), String>),
+    /// a Spotify call made in the background returned
+    Spotify(SpotifyResult),
+    /// a command received on the IPC socket
+    Ipc(IpcCommand),
+    /// the terminal input stopped
+    InputClosed(Option<String>),
+}
+
+/// Sends events to the bus, from the tasks doing long-running work.
+pub type Sender = mpsc::UnboundedSender<AppEvent>;
+
+/// Queue of the events from the terminal, the tasks and the ticks.
+#[derive(Debug)]
+pub struct EventBus {
+    tx: Sender,
+    rx: mpsc::UnboundedReceiver<AppEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self { tx, rx }
+    }
+}
+
+impl EventBus {
+    pub fn sender(&self) -> Sender {
+        self.tx.clone()
+    }
+
+    /// Forwards the terminal events until the terminal closes, needs a tokio runtime.
+    pub fn listen_terminal(&self) {
+        let tx = self.sender();
+        tokio::spawn(async move {
+            let mut stream = EventStream::new();
+            loop {
+                let event = match stream.next().await {
+                    Some(Ok(event)) => AppEvent::Input(event),
+                    Some(Err(e)) => AppEvent::InputClosed(Some(e.to_string())),
+                    None => AppEvent::InputClosed(None),
+                };
+                let closed = matches!(event, AppEvent::InputClosed(_));
+                if tx.send(event).is_err() || closed {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// The next event, or a tick when none comes within `timeout`.
+    pub async fn next(&mut self, timeout: Duration) -> AppEvent {
+        tokio::select! {
+            // never closed, the bus holds a sender
+            Some(event) = self.rx.recv() => event,
+            _ = tokio::time::sleep(timeout) => AppEvent::Tick,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn when_nothing_queued_should_tick_after_the_timeout() {
+        let mut bus = EventBus::default();
+        let start = Instant::now();
+        let timeout = Duration::from_millis(20);
+        assert!(matches!(bus.next(timeout).await, AppEvent::Tick));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        bus.sender().send(AppEvent::Audio(Ok(()))).unwrap();
+        let far = Duration::from_secs(60);
+        assert!(matches!(bus.next(far).await, AppEvent::Audio(Ok(()))));
+        // the event was taken, nothing is left but ticks
+        assert!(matches!(bus.next(timeout).await, AppEvent::Tick));
+    }
+}
@@ -1,34 +1,131 @@
-use std::time::Instant;
+use std::time::Duration;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Sparkline, Widget},
+};
+
+use crate::clock::{self, SharedClock};
+use crate::theme;
+
+/// Where the time of one frame went.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FrameTiming {
+    pub draw: Duration,
+    pub update: Duration,
+    pub event: Duration,
+    /// part of `draw` spent on the animated shape
+    pub shape: Duration,
+}
+
+impl FrameTiming {
+    pub fn total(&self) -> Duration {
+        self.draw + self.update + self.event
+    }
+}
+
+/// Frames kept for the overlay, a few seconds at 60 fps.
+pub const HISTORY: usize = 256;
 
 pub struct Fps {
-    last_frame_update: Instant,
+    clock: SharedClock,
+    last_frame_update: Duration, // reading of `clock`
     frame_count: u32,
+    busy: Duration, // time spent drawing since the last update
     fps: f64,
+    frame_time: Duration,
+    history: Vec<FrameTiming>, // ring buffer, `next` is the oldest once full
+    next: usize,
+    shapes: Vec<(String, Duration, u32)>, // total shape draw time and frames per shape
 }
 
 impl Fps {
     pub fn new() -> Self {
+        Self::with_clock(clock::system())
+    }
+
+    pub fn with_clock(clock: SharedClock) -> Self {
         Self {
-            last_frame_update: Instant::now(),
+            last_frame_update: clock.now(),
+            clock,
             frame_count: 0,
+            busy: Duration::ZERO,
             fps: 0.0,
+            frame_time: Duration::ZERO,
+            history: Vec::with_capacity(HISTORY),
+            next: 0,
+            shapes: vec![],
         }
     }
 
-    pub fn update(&mut self) {
+    /// Counts a frame that took `frame_time` to draw.
+    pub fn update(&mut self, frame_time: Duration) {
         self.frame_count += 1;
-        let now = Instant::now();
-        let elapsed = (now - self.last_frame_update).as_secs_f64();
+        self.busy += frame_time;
+        let now = self.clock.now();
+        let elapsed = now.saturating_sub(self.last_frame_update).as_secs_f64();
         if elapsed >= 1.0 {
             self.fps = self.frame_count as f64 / elapsed;
+            self.frame_time = self.busy / self.frame_count;
             self.last_frame_update = now;
             self.frame_count = 0;
+            self.busy = Duration::ZERO;
         }
     }
 
+    /// Keeps the timings of a frame, `shape` names what was animated.
+    pub fn record(&mut self, timing: FrameTiming, shape: &str) {
+        if self.history.len() < HISTORY {
+            self.history.push(timing);
+        } else {
+            self.history[self.next] = timing;
+        }
+        self.next = (self.next + 1) % HISTORY;
+
+        match self.shapes.iter_mut().find(|(name, _, _)| name == shape) {
+            Some((_, total, count)) => {
+                *total += timing.shape;
+                *count += 1;
+            }
+            None => self.shapes.push((shape.to_string(), timing.shape, 1)),
+        }
+    }
+
+    /// Recorded frames, oldest first.
+    pub fn history(&self) -> impl Iterator<Item = &FrameTiming> {
+        let (newest, oldest) = self.history.split_at(self.next % self.history.len().max(1));
+        oldest.iter().chain(newest)
+    }
+
+    /// `p` in `0.0..=1.0` of the recorded frames, measured with `f`.
+    pub fn percentile(&self, p: f64, f: impl Fn(&FrameTiming) -> Duration) -> Duration {
+        let mut values: Vec<Duration> = self.history.iter().map(f).collect();
+        if values.is_empty() {
+            return Duration::ZERO;
+        }
+        values.sort_unstable();
+        let i = ((values.len() - 1) as f64 * p.clamp(0.0, 1.0)).round() as usize;
+        values[i]
+    }
+
+    /// Mean draw time of every shape animated so far.
+    pub fn shape_costs(&self) -> impl Iterator<Item = (&str, Duration)> {
+        self.shapes
+            .iter()
+            .map(|(name, total, count)| (name.as_str(), *total / (*count).max(1)))
+    }
+
     pub fn fps(&self) -> f64 {
         self.fps
     }
+
+    /// Mean time to draw a frame over the last second.
+    pub fn frame_time(&self) -> Duration {
+        self.frame_time
+    }
 }
 
 impl Default for Fps {
@@ -36,3 +133,254 @@ impl Default for Fps {
         Self::new()
     }
 }
+
+fn ms(d: Duration) -> String {
+    format!("{:.2}ms", d.as_secs_f64() * 1000.0)
+}
+
+/// Frame time sparkline and percentiles, drawn over the animation.
+pub struct Overlay<'a>(pub &'a Fps, pub &'a theme::Popup);
+
+impl Widget for Overlay<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let (fps, theme) = (self.0, self.1);
+        Clear.render(area, buf);
+        let block = Block::bordered()
+            .title(" Performance (F12) ")
+            .style(theme.body);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let shapes = fps.shapes.len() as u16;
+        let [spark_area, stats_area] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(5 + shapes)]).areas(inner);
+
+        // newest frames on the right
+        let data: Vec<u64> = fps
+            .history()
+            .map(|t| t.total().as_micros() as u64)
+            .collect();
+        let shown = &data[data.len().saturating_sub(usize::from(spark_area.width))..];
+        Sparkline::default()
+            .data(shown)
+            .style(theme.ok)
+            .render(spark_area, buf);
+
+        let row = |label: &str, f: fn(&FrameTiming) -> Duration| {
+            Line::from(vec![
+                format!("{label:<8}").dim(),
+                format!(
+                    "p50 {:>9}  p99 {:>9}",
+                    ms(fps.percentile(0.5, f)),
+                    ms(fps.percentile(0.99, f))
+                )
+                .into(),
+            ])
+        };
+        let mut lines = vec![
+            Line::from(format!(
+                "{:.2} fps, {} frames",
+                fps.fps(),
+                fps.history.len()
+            )),
+            row("frame", |t| t.total()),
+            row("draw", |t| t.draw),
+            row("update", |t| t.update),
+            row("event", |t| t.event),
+        ];
+        lines.extend(fps.shape_costs().map(|(name, cost)| {
+            Line::from(vec![
+                format!("{name:<14}").dim(),
+                format!("mean {:>9}", ms(cost)).into(),
+            ])
+        }));
+        Paragraph::new(lines).render(stats_area, buf);
+    }
+}
+
+/// How the frame rate follows what is on screen.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum FrameMode {
+    /// always the requested frame rate
+    Fixed,
+    /// the requested frame rate while animating, a few fps otherwise
+    #[default]
+    Adaptive,
+    /// adaptive and capped, for laptops on battery
+    LowPower,
+}
+
+const IDLE_FPS: f64 = 4.0;
+const UNFOCUSED_FPS: f64 = 1.0;
+const LOW_POWER_FPS: f64 = 15.0;
+
+/// Picks the time between two frames.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FramePacer {
+    frame_rate: f64,
+    mode: FrameMode,
+    focused: bool,
+    idle: bool,
+}
+
+impl FramePacer {
+    pub fn new(frame_rate: f64, mode: FrameMode) -> Self {
+        Self {
+            frame_rate: frame_rate.max(UNFOCUSED_FPS),
+            mode,
+            focused: true,
+            idle: false,
+        }
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// Nothing visibly moves on screen.
+    pub fn set_idle(&mut self, idle: bool) {
+        self.idle = idle;
+    }
+
+    pub fn frame_rate(&self) -> f64 {
+        match self.mode {
+            FrameMode::Fixed => self.frame_rate,
+            _ if !self.focused => UNFOCUSED_FPS,
+            FrameMode::Adaptive if self.idle => IDLE_FPS,
+            FrameMode::Adaptive => self.frame_rate,
+            FrameMode::LowPower if self.idle => UNFOCUSED_FPS,
+            FrameMode::LowPower => self.frame_rate.min(LOW_POWER_FPS),
+        }
+    }
+
+    pub fn frame_time(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.frame_rate())
+    }
+}
+
+impl Default for FramePacer {
+    fn default() -> Self {
+        Self::new(60.0, FrameMode::default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn frame(ms: u64) -> FrameTiming {
+        FrameTiming {
+            draw: Duration::from_millis(ms),
+            shape: Duration::from_millis(ms / 2),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn when_history_is_full_should_drop_oldest() {
+        let mut fps = Fps::new();
+        for i in 0..HISTORY as u64 + 10 {
+            fps.record(frame(i), "arc");
+        }
+        let draws: Vec<u64> = fps.history().map(|t| t.draw.as_millis() as u64).collect();
+        assert_eq!(HISTORY, draws.len());
+        assert_eq!(10, draws[0]);
+        assert_eq!(HISTORY as u64 + 9, draws[HISTORY - 1]);
+    }
+
+    #[test]
+    fn when_recording_should_compute_percentiles_and_shape_costs() {
+        let mut fps = Fps::new();
+        assert_eq!(Duration::ZERO, fps.percentile(0.5, |t| t.draw));
+        for i in 1..=100 {
+            fps.record(frame(i), if i <= 50 { "arc" } else { "rain" });
+        }
+        assert_eq!(Duration::from_millis(51), fps.percentile(0.5, |t| t.draw));
+        assert_eq!(Duration::from_millis(99), fps.percentile(0.99, |t| t.draw));
+        let costs: Vec<_> = fps.shape_costs().collect();
+        assert_eq!(
+            vec![
+                ("arc", Duration::from_micros(12_500)),
+                ("rain", Duration::from_micros(37_500))
+            ],
+            costs
+        );
+    }
+
+    #[test]
+    fn when_rendering_overlay_should_show_percentiles() {
+        let mut fps = Fps::new();
+        for i in 0..20 {
+            fps.record(frame(i), "arc");
+        }
+        for area in [Rect::new(0, 0, 48, 16), Rect::new(0, 0, 5, 2)] {
+            let mut buf = Buffer::empty(area);
+            Overlay(&fps, &theme::LIGHT.popup).render(area, &mut buf);
+            if area.width == 48 {
+                assert_eq!(theme::LIGHT.popup.body.bg, buf[(0, 0)].style().bg);
+                let text: String = area
+                    .positions()
+                    .map(|p| buf[p].symbol().to_string())
+                    .collect();
+                assert!(text.contains("p99"));
+                assert!(text.contains("arc"));
+            }
+        }
+    }
+
+    #[test]
+    fn when_a_second_passed_should_count_frames_per_second() {
+        let manual = crate::clock::ManualClock::default();
+        let mut fps = Fps::with_clock(std::sync::Arc::new(manual.clone()));
+        for _ in 0..29 {
+            manual.advance(Duration::from_millis(25));
+            fps.update(Duration::from_millis(4));
+        }
+        assert_eq!(0.0, fps.fps());
+        manual.advance(Duration::from_millis(275));
+        fps.update(Duration::from_millis(10));
+        assert_eq!(30.0, fps.fps());
+        assert_eq!(Duration::from_micros(4200), fps.frame_time());
+    }
+
+    #[test]
+    fn when_fixed_should_ignore_idle_and_focus() {
+        let mut p = FramePacer::new(30.0, FrameMode::Fixed);
+        p.set_idle(true);
+        p.set_focused(false);
+        assert_eq!(30.0, p.frame_rate());
+    }
+
+    #[test]
+    fn when_adaptive_should_slow_down_when_idle_or_unfocused() {
+        let mut p = FramePacer::new(60.0, FrameMode::Adaptive);
+        assert_eq!(Duration::from_secs_f64(1.0 / 60.0), p.frame_time());
+        p.set_idle(true);
+        assert_eq!(IDLE_FPS, p.frame_rate());
+        p.set_focused(false);
+        assert_eq!(UNFOCUSED_FPS, p.frame_rate());
+        p.set_idle(false);
+        assert_eq!(UNFOCUSED_FPS, p.frame_rate());
+        p.set_focused(true);
+        assert_eq!(60.0, p.frame_rate());
+    }
+
+    #[test]
+    fn when_low_power_should_cap_frame_rate() {
+        let mut p = FramePacer::new(60.0, FrameMode::LowPower);
+        assert_eq!(LOW_POWER_FPS, p.frame_rate());
+        p.set_idle(true);
+        assert_eq!(UNFOCUSED_FPS, p.frame_rate());
+        assert_eq!(
+            10.0,
+            FramePacer::new(10.0, FrameMode::LowPower).frame_rate()
+        );
+    }
+
+    #[test]
+    fn when_frame_rate_is_zero_should_not_stall() {
+        let p = FramePacer::new(0.0, FrameMode::Fixed);
+        assert_eq!(Duration::from_secs(1), p.frame_time());
+    }
+}
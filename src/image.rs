@@ -0,0 +1,310 @@
+use std::path::Path;
+
+use color_eyre::{eyre::eyre, Result};
+
+/// Small grayscale picture used as a progress mask.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    luma: Vec<u8>,
+    alpha: Option<Vec<u8>>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, luma: Vec<u8>, alpha: Option<Vec<u8>>) -> Self {
+        Self {
+            width,
+            height,
+            luma,
+            alpha,
+        }
+    }
+
+    /// Loads a PNG or a PBM/PGM (plain or raw) file, picked from the content.
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path).map_err(|e| eyre!("{}: {e}", path.display()))?;
+        Self::decode(&data).map_err(|e| eyre!("{}: {e}", path.display()))
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        if data.starts_with(b"\x89PNG") {
+            decode_png(data)
+        } else if data.first() == Some(&b'P') {
+            decode_netpbm(data)
+        } else {
+            Err(eyre!("unsupported image format, expected PNG, PBM or PGM"))
+        }
+    }
+
+    /// Part of the drawing: opaque pixels when the image has transparency, dark
+    /// pixels otherwise. `invert` flips it for light drawings on dark backgrounds.
+    pub fn is_ink(&self, x: usize, y: usize, invert: bool) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let i = y * self.width + x;
+        let ink = match &self.alpha {
+            Some(alpha) => alpha[i] >= 128,
+            None => self.luma[i] < 128,
+        };
+        ink != invert
+    }
+}
+
+fn decode_png(data: &[u8]) -> Result<Image> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf)?;
+    let (width, height) = (info.width as usize, info.height as usize);
+    let buf = &buf[..info.buffer_size()];
+
+    let luma = |r: u8, g: u8, b: u8| {
+        (0.299 * f64::from(r) + 0.587 * f64::from(g) + 0.114 * f64::from(b)).round() as u8
+    };
+    let (luma, alpha): (Vec<u8>, Option<Vec<u8>>) = match info.color_type {
+        png::ColorType::Grayscale => (buf.to_vec(), None),
+        png::ColorType::GrayscaleAlpha => (
+            buf.chunks(2).map(|p| p[0]).collect(),
+            Some(buf.chunks(2).map(|p| p[1]).collect()),
+        ),
+        png::ColorType::Rgb => (
+            buf.chunks(3).map(|p| luma(p[0], p[1], p[2])).collect(),
+            None,
+        ),
+        png::ColorType::Rgba => (
+            buf.chunks(4).map(|p| luma(p[0], p[1], p[2])).collect(),
+            Some(buf.chunks(4).map(|p| p[3]).collect()),
+        ),
+        png::ColorType::Indexed => return Err(eyre!("indexed PNG was not expanded")),
+    };
+    // fully opaque images are treated as having no alpha
+    let alpha = alpha.filter(|a| a.iter().any(|v| *v < 255));
+    Ok(Image::new(width, height, luma, alpha))
+}
+
+/// Largest width or height accepted from a netpbm header, a mask only needs a few
+/// hundred pixels.
+const MAX_SIDE: usize = 8192;
+/// Largest maxval of the netpbm formats.
+const MAX_VALUE: usize = 65535;
+
+// header tokens are separated by whitespace, `#` starts a comment up to the end of line
+struct Tokens<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn skip_blank(&mut self) {
+        while let Some(&c) = self.data.get(self.pos) {
+            if c == b'#' {
+                while self.data.get(self.pos).is_some_and(|c| *c != b'\n') {
+                    self.pos += 1;
+                }
+            } else if c.is_ascii_whitespace() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        self.skip_blank();
+        let start = self.pos;
+        while self
+            .data
+            .get(self.pos)
+            .is_some_and(|c| !c.is_ascii_whitespace() && *c != b'#')
+        {
+            self.pos += 1;
+        }
+        (self.pos > start).then(|| &self.data[start..self.pos])
+    }
+
+    fn number(&mut self) -> Result<usize> {
+        let t = self.next().ok_or_else(|| eyre!("truncated header"))?;
+        std::str::from_utf8(t)?
+            .parse()
+            .map_err(|_| eyre!("invalid number in header"))
+    }
+
+    // plain PBM pixels can be written without separators: `0110`
+    fn bit(&mut self) -> Result<u8> {
+        self.skip_blank();
+        let c = self
+            .data
+            .get(self.pos)
+            .ok_or_else(|| eyre!("not enough pixels"))?;
+        self.pos += 1;
+        match c {
+            b'0' => Ok(0),
+            b'1' => Ok(1),
+            _ => Err(eyre!("invalid PBM pixel")),
+        }
+    }
+}
+
+fn decode_netpbm(data: &[u8]) -> Result<Image> {
+    let mut tokens = Tokens { data, pos: 0 };
+    let magic = tokens.next().ok_or_else(|| eyre!("empty file"))?;
+    let width = tokens.number()?;
+    let height = tokens.number()?;
+    if width > MAX_SIDE || height > MAX_SIDE {
+        return Err(eyre!(
+            "image of {width}x{height} is too large, at most {MAX_SIDE}x{MAX_SIDE}"
+        ));
+    }
+    let count = width
+        .checked_mul(height)
+        .ok_or_else(|| eyre!("image of {width}x{height} is too large"))?;
+    let luma: Vec<u8> = match magic {
+        b"P1" => (0..count)
+            .map(|_| tokens.bit().map(|b| if b == 1 { 0 } else { 255 }))
+            .collect::<Result<_>>()?,
+        b"P2" => {
+            let max = max_value(&mut tokens)?;
+            (0..count)
+                .map(|_| tokens.number().map(|v| (v.min(max) * 255 / max) as u8))
+                .collect::<Result<_>>()?
+        }
+        b"P4" => {
+            // a single whitespace then packed rows, padded to a byte
+            let start = tokens.pos + 1;
+            let row_bytes = width.div_ceil(8);
+            let end = row_bytes
+                .checked_mul(height)
+                .and_then(|n| n.checked_add(start))
+                .ok_or_else(|| eyre!("not enough pixels"))?;
+            let raw = data
+                .get(start..end)
+                .ok_or_else(|| eyre!("not enough pixels"))?;
+            (0..count)
+                .map(|i| {
+                    let (x, y) = (i % width, i / width);
+                    let bit = raw[y * row_bytes + x / 8] >> (7 - x % 8) & 1;
+                    if bit == 1 {
+                        0
+                    } else {
+                        255
+                    }
+                })
+                .collect()
+        }
+        b"P5" => {
+            let max = max_value(&mut tokens)?;
+            if max > 255 {
+                return Err(eyre!("16 bit PGM is not supported"));
+            }
+            let start = tokens.pos + 1;
+            let end = start
+                .checked_add(count)
+                .ok_or_else(|| eyre!("not enough pixels"))?;
+            let raw = data
+                .get(start..end)
+                .ok_or_else(|| eyre!("not enough pixels"))?;
+            raw.iter()
+                .map(|v| (usize::from(*v).min(max) * 255 / max) as u8)
+                .collect()
+        }
+        _ => {
+            return Err(eyre!(
+                "unsupported netpbm format, expected P1, P2, P4 or P5"
+            ))
+        }
+    };
+    Ok(Image::new(width, height, luma, None))
+}
+
+// keeps the scaling of the gray levels `v * 255 / max` in range
+fn max_value(tokens: &mut Tokens) -> Result<usize> {
+    match tokens.number()? {
+        max if max > MAX_VALUE => Err(eyre!("maxval {max} is above {MAX_VALUE}")),
+        max => Ok(max.max(1)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn ink_rows(img: &Image) -> Vec<String> {
+        (0..img.height)
+            .map(|y| {
+                (0..img.width)
+                    .map(|x| if img.is_ink(x, y, false) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn when_plain_pbm_should_decode() {
+        let img = Image::decode(b"P1\n# comment\n3 2\n1 0 1\n010").unwrap();
+        assert_eq!(vec!["#.#", ".#."], ink_rows(&img));
+    }
+
+    #[test]
+    fn when_raw_pbm_should_decode() {
+        let img = Image::decode(b"P4\n10 2\n\xc0\x40\x01\x80").unwrap();
+        assert_eq!(vec!["##.......#", ".......##."], ink_rows(&img));
+    }
+
+    #[test]
+    fn when_plain_pgm_should_scale_to_max() {
+        let img = Image::decode(b"P2 2 1 15 0 15").unwrap();
+        assert_eq!(vec!["#."], ink_rows(&img));
+    }
+
+    #[test]
+    fn when_raw_pgm_should_decode() {
+        let img = Image::decode(b"P5\n3 1\n255\n\x00\x80\xff").unwrap();
+        assert_eq!(vec!["#.."], ink_rows(&img));
+    }
+
+    #[test]
+    fn when_png_with_alpha_should_use_opacity() {
+        let mut data = vec![];
+        {
+            let mut encoder = png::Encoder::new(&mut data, 2, 1);
+            encoder.set_color(png::ColorType::Rgba);
+            let mut writer = encoder.write_header().unwrap();
+            writer
+                .write_image_data(&[255, 255, 255, 255, 0, 0, 0, 0])
+                .unwrap();
+        }
+        let img = Image::decode(&data).unwrap();
+        assert_eq!(vec!["#."], ink_rows(&img));
+        assert_eq!(vec![".#"], ink_rows(&Image { alpha: None, ..img }));
+    }
+
+    #[test]
+    fn when_truncated_should_fail() {
+        assert!(Image::decode(b"P1 3 3 1 0").is_err());
+        assert!(Image::decode(b"P5 3 3 255\n\x00").is_err());
+        assert!(Image::decode(b"GIF89a").is_err());
+    }
+
+    #[test]
+    fn when_header_is_too_large_should_fail() {
+        for data in [
+            &b"P5 99999999999 99999999999 255\n\x00"[..],
+            b"P4 18446744073709551615 2\n\x00",
+            b"P1 9000 1 0",
+            b"P2 1 1 18446744073709551615 7",
+        ] {
+            assert!(
+                Image::decode(data).is_err(),
+                "{}",
+                String::from_utf8_lossy(data)
+            );
+        }
+        // still room for a large mask
+        let img = Image::decode(b"P2 2 1 65535 0 65535").unwrap();
+        assert_eq!(vec!["#."], ink_rows(&img));
+    }
+}
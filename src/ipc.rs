@@ -0,0 +1,166 @@
+use std::{path::PathBuf, str::FromStr, time::Duration};
+
+use color_eyre::{eyre::eyre, Result};
+
+use crate::cli;
+use crate::event::{AppEvent, Sender};
+use crate::keymap::Action;
+
+/// A line received on the IPC socket, e.g. `pause` or `set 25m`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpcCommand {
+    /// a keymap action by its name in the `[keys]` table
+    Action(Action),
+    /// starts over with a new duration
+    Set(Duration),
+}
+
+impl FromStr for IpcCommand {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Some(d) = s.strip_prefix("set ") {
+            return cli::parse_duration(d.trim())
+                .filter(|d| !d.is_zero())
+                .map(IpcCommand::Set)
+                .ok_or_else(|| eyre!("invalid duration `{}`, e.g. 1h30m or 90s", d.trim()));
+        }
+        Action::from_name(s)
+            .map(IpcCommand::Action)
+            .ok_or_else(|| eyre!("unknown command `{s}`, expected `set <duration>` or an action"))
+    }
+}
+
+/// Accepts connections on the unix socket `path`, each line is a command
+/// answered with `ok` or `error: ...`. Needs a tokio runtime.
+#[cfg(unix)]
+pub fn listen(path: PathBuf, tx: Sender) -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::UnixListener;
+
+    use std::os::unix::fs::FileTypeExt;
+
+    // a socket left by a previous run, anything else is not ours to remove
+    if let Ok(meta) = std::fs::symlink_metadata(&path) {
+        if !meta.file_type().is_socket() {
+            return Err(eyre!("{}: exists and is not a socket", path.display()));
+        }
+        match std::os::unix::net::UnixStream::connect(&path) {
+            Ok(_) => return Err(eyre!("another instance is listening on {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                std::fs::remove_file(&path).map_err(|e| eyre!("{}: {e}", path.display()))?;
+            }
+            Err(e) => return Err(eyre!("{}: {e}", path.display())),
+        }
+    }
+    let listener = UnixListener::bind(&path).map_err(|e| eyre!("{}: {e}", path.display()))?;
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let (read, mut write) = stream.into_split();
+                let mut lines = BufReader::new(read).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let reply = match line.parse::<IpcCommand>() {
+                        Ok(cmd) => match tx.send(AppEvent::Ipc(cmd)) {
+                            Ok(()) => "ok\n".to_string(),
+                            // the app is gone
+                            Err(_) => return,
+                        },
+                        Err(e) => format!("error: {e}\n"),
+                    };
+                    if write.write_all(reply.as_bytes()).await.is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn listen(_path: PathBuf, _tx: Sender) -> Result<()> {
+    Err(eyre!("the IPC socket needs a unix system"))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn when_parsing_should_read_actions_and_durations() {
+        assert_eq!(
+            IpcCommand::Action(Action::Pause),
+            "pause\n".parse().unwrap()
+        );
+        assert_eq!(
+            IpcCommand::Action(Action::AddLarge),
+            "add-large".parse().unwrap()
+        );
+        assert_eq!(
+            IpcCommand::Set(Duration::from_secs(25 * 60)),
+            "set 25m".parse().unwrap()
+        );
+        for s in ["", "nap", "set", "set 0s", "set soon"] {
+            assert!(s.parse::<IpcCommand>().is_err(), "{s}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn when_path_is_not_a_socket_should_keep_it() {
+        let path = std::env::temp_dir().join(format!("crb-alarm-{}.toml", std::process::id()));
+        std::fs::write(&path, "duration = \"25m\"").unwrap();
+        let bus = crate::event::EventBus::default();
+        let error = listen(path.clone(), bus.sender()).unwrap_err();
+        assert!(error.to_string().contains("not a socket"), "{error}");
+        assert_eq!(
+            "duration = \"25m\"",
+            std::fs::read_to_string(&path).unwrap()
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn when_socket_is_live_should_leave_it_to_its_instance() {
+        let path = std::env::temp_dir().join(format!("crb-alarm-{}-live.sock", std::process::id()));
+        // left by a run that is gone, nobody answers on it
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        let bus = crate::event::EventBus::default();
+        listen(path.clone(), bus.sender()).unwrap();
+
+        let second = crate::event::EventBus::default();
+        let error = listen(path.clone(), second.sender()).unwrap_err();
+        assert!(error.to_string().contains("another instance"), "{error}");
+        assert!(tokio::net::UnixStream::connect(&path).await.is_ok());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn when_connected_should_forward_commands_and_answer() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let path = std::env::temp_dir().join(format!("crb-alarm-{}.sock", std::process::id()));
+        let mut bus = crate::event::EventBus::default();
+        listen(path.clone(), bus.sender()).unwrap();
+        let stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        let (read, mut write) = stream.into_split();
+        let mut replies = BufReader::new(read).lines();
+
+        write.write_all(b"pause\nfly\n").await.unwrap();
+        assert_eq!("ok", replies.next_line().await.unwrap().unwrap());
+        let error = replies.next_line().await.unwrap().unwrap();
+        assert!(error.starts_with("error: unknown command `fly`"), "{error}");
+        let far = Duration::from_secs(5);
+        assert!(matches!(
+            bus.next(far).await,
+            AppEvent::Ipc(IpcCommand::Action(Action::Pause))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+}
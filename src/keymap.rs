@@ -0,0 +1,575 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use clap::ValueEnum;
+use color_eyre::{eyre::eyre, Result};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Where an action applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    /// the countdown
+    Main,
+    /// the music player and preset lists
+    List,
+    /// every screen
+    Global,
+}
+
+/// Something a key does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    AddSmall,
+    SubSmall,
+    AddMedium,
+    SubMedium,
+    AddLarge,
+    SubLarge,
+    Pause,
+    Reset,
+    Restart,
+    RepeatLast,
+    Snooze,
+    EditDuration,
+    Presets,
+    MusicPlayer,
+    Palette,
+    Quit,
+    Up,
+    Down,
+    First,
+    Last,
+    Select,
+    Deselect,
+    Back,
+    Perf,
+    Help,
+}
+
+impl Action {
+    pub const ALL: [Action; 25] = [
+        Action::AddSmall,
+        Action::SubSmall,
+        Action::AddMedium,
+        Action::SubMedium,
+        Action::AddLarge,
+        Action::SubLarge,
+        Action::Pause,
+        Action::Reset,
+        Action::Restart,
+        Action::RepeatLast,
+        Action::Snooze,
+        Action::EditDuration,
+        Action::Presets,
+        Action::MusicPlayer,
+        Action::Palette,
+        Action::Quit,
+        Action::Up,
+        Action::Down,
+        Action::First,
+        Action::Last,
+        Action::Select,
+        Action::Deselect,
+        Action::Back,
+        Action::Perf,
+        Action::Help,
+    ];
+
+    /// Name in the `[keys]` table.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::AddSmall => "add-small",
+            Action::SubSmall => "sub-small",
+            Action::AddMedium => "add-medium",
+            Action::SubMedium => "sub-medium",
+            Action::AddLarge => "add-large",
+            Action::SubLarge => "sub-large",
+            Action::Pause => "pause",
+            Action::Reset => "reset",
+            Action::Restart => "restart",
+            Action::RepeatLast => "repeat-last",
+            Action::Snooze => "snooze",
+            Action::EditDuration => "edit-duration",
+            Action::Presets => "presets",
+            Action::MusicPlayer => "music-player",
+            Action::Palette => "palette",
+            Action::Quit => "quit",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::First => "first",
+            Action::Last => "last",
+            Action::Select => "select",
+            Action::Deselect => "deselect",
+            Action::Back => "back",
+            Action::Perf => "perf",
+            Action::Help => "help",
+        }
+    }
+
+    /// Shown in the bottom bar and the help.
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::AddSmall => "Add small step",
+            Action::SubSmall => "Sub small step",
+            Action::AddMedium => "Add medium step",
+            Action::SubMedium => "Sub medium step",
+            Action::AddLarge => "Add large step",
+            Action::SubLarge => "Sub large step",
+            Action::Pause => "Pause",
+            Action::Reset => "Reset",
+            Action::Restart => "Restart",
+            Action::RepeatLast => "Repeat last",
+            Action::Snooze => "Snooze",
+            Action::EditDuration => "Set time",
+            Action::Presets => "Presets",
+            Action::MusicPlayer => "Music player",
+            Action::Palette => "Commands",
+            Action::Quit => "Quit",
+            Action::Up => "Up",
+            Action::Down => "Down",
+            Action::First => "First",
+            Action::Last => "Last",
+            Action::Select => "Select",
+            Action::Deselect => "Deselect",
+            Action::Back => "Back",
+            Action::Perf => "Perf",
+            Action::Help => "Help",
+        }
+    }
+
+    pub fn context(self) -> Context {
+        match self {
+            Action::AddSmall
+            | Action::SubSmall
+            | Action::AddMedium
+            | Action::SubMedium
+            | Action::AddLarge
+            | Action::SubLarge
+            | Action::Pause
+            | Action::Reset
+            | Action::Restart
+            | Action::RepeatLast
+            | Action::Snooze
+            | Action::EditDuration
+            | Action::Presets
+            | Action::MusicPlayer
+            | Action::Palette
+            | Action::Quit => Context::Main,
+            Action::Up
+            | Action::Down
+            | Action::First
+            | Action::Last
+            | Action::Select
+            | Action::Deselect
+            | Action::Back => Context::List,
+            Action::Perf | Action::Help => Context::Global,
+        }
+    }
+
+    /// The action named `name` in the `[keys]` table.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+}
+
+/// A key with its modifiers, written `Ctrl+p`, `Alt+Left`, `F12` or `?`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        let mut modifiers = key.modifiers;
+        // the case of a letter already says if shift is down
+        if matches!(key.code, KeyCode::Char(_)) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        self.code == key.code && self.modifiers == modifiers
+    }
+}
+
+const NAMED_KEYS: [(&str, KeyCode); 13] = [
+    ("Esc", KeyCode::Esc),
+    ("Enter", KeyCode::Enter),
+    ("Tab", KeyCode::Tab),
+    ("Backspace", KeyCode::Backspace),
+    ("Space", KeyCode::Char(' ')),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+];
+
+impl FromStr for KeyChord {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        // `Ctrl++` is ctrl and plus, the key is never empty
+        while let Some((m, tail)) = rest.split_once('+').filter(|(_, t)| !t.is_empty()) {
+            modifiers |= match m.to_ascii_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(eyre!("unknown modifier `{m}` in `{s}`")),
+            };
+            rest = tail;
+        }
+        let named = NAMED_KEYS
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(rest))
+            .map(|(_, c)| *c);
+        let code = match named {
+            Some(c) => c,
+            None if rest.chars().count() == 1 => KeyCode::Char(rest.chars().next().unwrap()),
+            None => match rest.strip_prefix(['F', 'f']).and_then(|n| n.parse().ok()) {
+                Some(n @ 1..=24) => KeyCode::F(n),
+                _ => return Err(eyre!("unknown key `{rest}` in `{s}`")),
+            },
+        };
+        // shift is never kept for a letter, `matches` reads it from the case
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            code => code,
+        };
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (m, name) in [
+            (KeyModifiers::CONTROL, "Ctrl+"),
+            (KeyModifiers::ALT, "Alt+"),
+            (KeyModifiers::SHIFT, "Shift+"),
+        ] {
+            if self.modifiers.contains(m) {
+                f.write_str(name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Left => f.write_str("←"),
+            KeyCode::Right => f.write_str("→"),
+            KeyCode::Up => f.write_str("↑"),
+            KeyCode::Down => f.write_str("↓"),
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            code => match NAMED_KEYS.iter().find(|(_, c)| *c == code) {
+                Some((name, _)) => f.write_str(name),
+                None => write!(f, "{code:?}"),
+            },
+        }
+    }
+}
+
+/// Base layout the `[keys]` table starts from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum KeymapPreset {
+    /// hjkl and the arrows
+    #[default]
+    Vim,
+    /// Ctrl+f/b/n/p and the arrows
+    Emacs,
+    /// the arrows only
+    Arrows,
+}
+
+impl KeymapPreset {
+    fn bindings(self) -> &'static [(Action, &'static str)] {
+        match self {
+            KeymapPreset::Vim => &[
+                (Action::SubSmall, "h Left"),
+                (Action::AddSmall, "l Right"),
+                (Action::AddMedium, "k Up"),
+                (Action::SubMedium, "j Down"),
+                (Action::AddLarge, "K Shift+Up Shift+Right"),
+                (Action::SubLarge, "J Shift+Down Shift+Left"),
+                (Action::Pause, "Space"),
+                (Action::Reset, "r"),
+                (Action::Restart, "R"),
+                (Action::RepeatLast, "."),
+                (Action::Snooze, "z"),
+                (Action::EditDuration, ": e"),
+                (Action::Presets, "p"),
+                (Action::MusicPlayer, "m"),
+                (Action::Palette, "Ctrl+p"),
+                (Action::Quit, "q"),
+                (Action::Up, "k Up"),
+                (Action::Down, "j Down"),
+                (Action::First, "g Home"),
+                (Action::Last, "G End"),
+                (Action::Select, "l Right Enter"),
+                (Action::Deselect, "h Left"),
+                (Action::Back, "q Esc"),
+                (Action::Help, "?"),
+                (Action::Perf, "F12"),
+            ],
+            KeymapPreset::Emacs => &[
+                (Action::SubSmall, "Ctrl+b Left"),
+                (Action::AddSmall, "Ctrl+f Right"),
+                (Action::AddMedium, "Alt+f Up"),
+                (Action::SubMedium, "Alt+b Down"),
+                (Action::AddLarge, "Shift+Up Shift+Right"),
+                (Action::SubLarge, "Shift+Down Shift+Left"),
+                (Action::Pause, "Space"),
+                (Action::Reset, "Alt+r"),
+                (Action::Restart, "Alt+s"),
+                (Action::RepeatLast, "Alt+."),
+                (Action::Snooze, "Alt+z"),
+                (Action::EditDuration, "Alt+e"),
+                (Action::Presets, "Alt+p"),
+                (Action::MusicPlayer, "Alt+m"),
+                (Action::Palette, "Alt+x"),
+                (Action::Quit, "Ctrl+c q"),
+                (Action::Up, "Ctrl+p Up"),
+                (Action::Down, "Ctrl+n Down"),
+                (Action::First, "Alt+< Home"),
+                (Action::Last, "Alt+> End"),
+                (Action::Select, "Enter Right"),
+                (Action::Deselect, "Left"),
+                (Action::Back, "Ctrl+g Esc q"),
+                (Action::Help, "F1 ?"),
+                (Action::Perf, "F12"),
+            ],
+            KeymapPreset::Arrows => &[
+                (Action::SubSmall, "Left"),
+                (Action::AddSmall, "Right"),
+                (Action::AddMedium, "Up"),
+                (Action::SubMedium, "Down"),
+                (Action::AddLarge, "Shift+Up Shift+Right"),
+                (Action::SubLarge, "Shift+Down Shift+Left"),
+                (Action::Pause, "Space"),
+                (Action::Reset, "r"),
+                (Action::Restart, "R"),
+                (Action::RepeatLast, "."),
+                (Action::Snooze, "z"),
+                (Action::EditDuration, "e"),
+                (Action::Presets, "p"),
+                (Action::MusicPlayer, "m"),
+                (Action::Palette, "Ctrl+p"),
+                (Action::Quit, "q Esc"),
+                (Action::Up, "Up"),
+                (Action::Down, "Down"),
+                (Action::First, "Home"),
+                (Action::Last, "End"),
+                (Action::Select, "Enter Right"),
+                (Action::Deselect, "Left"),
+                (Action::Back, "Esc q"),
+                (Action::Help, "?"),
+                (Action::Perf, "F12"),
+            ],
+        }
+    }
+}
+
+// space separated chords
+fn parse_keys(keys: &str) -> Result<Vec<KeyChord>> {
+    keys.split_whitespace().map(str::parse).collect()
+}
+
+/// Keys of every action, built from a [`KeymapPreset`] and the `[keys]` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: BTreeMap<Action, Vec<KeyChord>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::preset(KeymapPreset::default())
+    }
+}
+
+impl Keymap {
+    pub fn preset(preset: KeymapPreset) -> Self {
+        let bindings = preset
+            .bindings()
+            .iter()
+            .map(|(action, keys)| (*action, parse_keys(keys).expect("valid preset keys")))
+            .collect();
+        Self { bindings }
+    }
+
+    /// The `[keys]` table of the configuration:
+    ///
+    /// ```toml
+    /// [keys]
+    /// preset = "emacs"
+    /// quit = "q Ctrl+c"
+    /// music-player = "Ctrl+o"
+    /// ```
+    ///
+    /// An action listed replaces all the keys the preset gives it.
+    pub fn from_config(keys: &BTreeMap<String, String>) -> Result<Self> {
+        let preset = match keys.get("preset") {
+            Some(p) => KeymapPreset::from_str(p, true)
+                .map_err(|_| eyre!("unknown keymap preset `{p}`, expected vim, emacs or arrows"))?,
+            None => KeymapPreset::default(),
+        };
+        let mut keymap = Self::preset(preset);
+        for (name, value) in keys.iter().filter(|(n, _)| *n != "preset") {
+            let action = Action::from_name(name).ok_or_else(|| {
+                let names: Vec<_> = Action::ALL.iter().map(|a| a.name()).collect();
+                eyre!(
+                    "unknown action `{name}`, expected one of {}",
+                    names.join(", ")
+                )
+            })?;
+            let chords = parse_keys(value).map_err(|e| eyre!("keys.{name}: {e}"))?;
+            keymap.bindings.insert(action, chords);
+        }
+        Ok(keymap)
+    }
+
+    /// Action of `key` in `context`, the global actions apply everywhere.
+    pub fn action(&self, context: Context, key: &KeyEvent) -> Option<Action> {
+        let find = |ctx: Context| {
+            self.bindings
+                .iter()
+                .filter(|(a, _)| a.context() == ctx)
+                .find(|(_, keys)| keys.iter().any(|k| k.matches(key)))
+                .map(|(a, _)| *a)
+        };
+        find(context).or_else(|| find(Context::Global))
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyChord] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// `(keys, action)` for the actions of `context`, keys joined by `/`,
+    /// unbound actions left out.
+    pub fn help(&self, context: Context) -> Vec<(String, Action)> {
+        Action::ALL
+            .into_iter()
+            .filter(|a| a.context() == context && !self.keys(*a).is_empty())
+            .map(|a| {
+                let keys: Vec<String> = self.keys(a).iter().map(KeyChord::to_string).collect();
+                (keys.join("/"), a)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn when_parsing_chords_should_read_modifiers_and_names() {
+        let ctrl_p: KeyChord = "Ctrl+p".parse().unwrap();
+        assert_eq!(
+            KeyChord::new(KeyCode::Char('p'), KeyModifiers::CONTROL),
+            ctrl_p
+        );
+        assert_eq!(KeyCode::F(12), "F12".parse::<KeyChord>().unwrap().code);
+        assert_eq!(
+            KeyCode::Char('+'),
+            "Ctrl++".parse::<KeyChord>().unwrap().code
+        );
+        assert_eq!(KeyCode::Left, "alt+left".parse::<KeyChord>().unwrap().code);
+        for s in ["Hyper+x", "Foo", "F99", ""] {
+            assert!(s.parse::<KeyChord>().is_err(), "{s}");
+        }
+        for s in ["Ctrl+p", "Alt+Shift+←", "F12", "Esc", "G", "Space"] {
+            let display = s.replace('←', "Left");
+            assert_eq!(s, display.parse::<KeyChord>().unwrap().to_string());
+        }
+    }
+
+    #[test]
+    fn when_shift_with_a_letter_should_read_the_upper_case() {
+        let shift_a: KeyChord = "Shift+a".parse().unwrap();
+        assert_eq!(
+            KeyChord::new(KeyCode::Char('A'), KeyModifiers::NONE),
+            shift_a
+        );
+        assert_eq!("A", shift_a.to_string());
+        assert!(shift_a.matches(&key(KeyCode::Char('A'), KeyModifiers::SHIFT)));
+        assert!(!shift_a.matches(&key(KeyCode::Char('a'), KeyModifiers::NONE)));
+        let ctrl_shift_a: KeyChord = "Ctrl+Shift+a".parse().unwrap();
+        assert!(ctrl_shift_a.matches(&key(
+            KeyCode::Char('A'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT
+        )));
+    }
+
+    #[test]
+    fn when_matching_should_use_context_then_global() {
+        let vim = Keymap::default();
+        let j = key(KeyCode::Char('j'), KeyModifiers::NONE);
+        assert_eq!(Some(Action::SubMedium), vim.action(Context::Main, &j));
+        assert_eq!(Some(Action::Down), vim.action(Context::List, &j));
+        let f12 = key(KeyCode::F(12), KeyModifiers::NONE);
+        assert_eq!(Some(Action::Perf), vim.action(Context::List, &f12));
+        // some terminals report shift with upper case letters
+        let g = key(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(Some(Action::Last), vim.action(Context::List, &g));
+        let ctrl_p = key(KeyCode::Char('p'), KeyModifiers::CONTROL);
+        assert_eq!(Some(Action::Palette), vim.action(Context::Main, &ctrl_p));
+        let p = key(KeyCode::Char('p'), KeyModifiers::NONE);
+        assert_eq!(Some(Action::Presets), vim.action(Context::Main, &p));
+    }
+
+    #[test]
+    fn when_presets_should_bind_every_action_without_conflict() {
+        for preset in KeymapPreset::value_variants() {
+            let keymap = Keymap::preset(*preset);
+            for a in Action::ALL {
+                assert!(!keymap.keys(a).is_empty(), "{preset:?} {a:?}");
+                for b in Action::ALL.into_iter().filter(|b| *b > a) {
+                    let shared = a.context() == b.context()
+                        || a.context() == Context::Global
+                        || b.context() == Context::Global;
+                    let overlap = keymap.keys(a).iter().any(|k| keymap.keys(b).contains(k));
+                    assert!(!(shared && overlap), "{preset:?} {a:?} {b:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn when_configured_should_override_preset() {
+        let keys: BTreeMap<String, String> =
+            [("preset", "emacs"), ("quit", "Ctrl+q"), ("help", "F1")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+        let keymap = Keymap::from_config(&keys).unwrap();
+        let q = key(KeyCode::Char('q'), KeyModifiers::NONE);
+        assert_eq!(None, keymap.action(Context::Main, &q));
+        let ctrl_f = key(KeyCode::Char('f'), KeyModifiers::CONTROL);
+        assert_eq!(
+            Some(Action::AddSmall),
+            keymap.action(Context::Main, &ctrl_f)
+        );
+        let help = keymap.help(Context::Main);
+        assert!(
+            help.contains(&("Ctrl+q".to_string(), Action::Quit)),
+            "{help:?}"
+        );
+
+        for (k, v) in [("preset", "nano"), ("jump", "x"), ("quit", "Ctrl+")] {
+            let keys = BTreeMap::from([(k.to_string(), v.to_string())]);
+            assert!(Keymap::from_config(&keys).is_err(), "{k} = {v}");
+        }
+    }
+}
@@ -1,8 +1,18 @@
 pub mod anime;
 pub mod app;
 pub mod cli;
+pub mod clock;
+pub mod config;
+pub mod event;
 pub mod fps;
+pub mod image;
+pub mod ipc;
+pub mod keymap;
+pub mod marker;
 pub mod music_player;
+pub mod palette;
+pub mod prompt;
 pub mod shapes;
+pub mod sound;
 pub mod spoty;
 pub mod theme;
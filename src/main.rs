@@ -3,22 +3,89 @@ use clap::Parser;
 use color_eyre::Result;
 
 use cbr_alarm::app;
-use std::time::Duration;
+use cbr_alarm::config::{self, Config};
+use cbr_alarm::image::Image;
+use cbr_alarm::marker::{self, MarkerPolicy, TermCaps};
+use cbr_alarm::shapes::{Custom, Gradient};
+use cbr_alarm::theme::{Theme, ThemeWatcher};
+use std::path::PathBuf;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = cli::Cli::parse();
 
-    let mut tm_s = Duration::from_secs(5);
-    if let Some(cmd) = args.cmd {
-        tm_s = match cmd {
-            cli::Commands::Timeout(t) => t.parse().unwrap(),
-        };
+    color_eyre::install()?;
+    let markers = MarkerPolicy {
+        forced: args.marker,
+        caps: TermCaps::from_env(),
+    };
+
+    match &args.cmd {
+        Some(cli::Commands::Markers) => {
+            let result = marker::run_preview(ratatui::init(), markers);
+            ratatui::restore();
+            return result;
+        }
+        Some(cli::Commands::Config { cmd }) => return config::run(*cmd, &args),
+        _ => {}
     }
+    let config = Config::resolve(&args)?;
 
-    color_eyre::install()?;
+    let image = args.image.as_deref().map(Image::load).transpose()?;
+    let (theme, theme_watcher) = match Theme::builtin(config.theme_name()) {
+        Some(theme) => (theme, None),
+        None => {
+            let path = PathBuf::from(config.theme_name());
+            (Theme::load(&path)?, Some(ThemeWatcher::new(path)))
+        }
+    };
+    let custom = match config.shapes_path() {
+        Some(path) => Custom::load_all(&path, theme.canvas.fg)?,
+        None => vec![],
+    };
+    // fail early on a typo rather than falling back on a random shape
+    let shape = config.shape(&custom, theme.canvas.fg)?;
+    let name = config::preset_name(&args)?.unwrap_or_default();
+    let timer = config.timer(name)?;
+    let presets = config.presets()?;
+    let gradient = config.gradient()?;
+    let keymap = config.keymap()?;
+    let steps = config.steps()?;
+    let urgency = config.urgency()?;
+    let depth = config.color().depth();
     let terminal = ratatui::init();
-    let app_result = app::App::new(tm_s).run(terminal).await;
+    let mut app = app::App::new(timer)
+        .with_clock(args.clock.clock())
+        .with_theme(theme)
+        .with_urgency(urgency)
+        .with_easing(args.easing)
+        .with_adjust(config.adjust())
+        .with_steps(steps)
+        .with_markers(markers)
+        .with_frame_rate(args.frame_rate, args.frame_mode)
+        .with_color_depth(depth)
+        .with_mouse(!args.no_mouse)
+        .with_custom_shapes(custom)
+        .with_spotify(&config.spotify)
+        .with_presets(presets)
+        .with_keymap(keymap);
+    if let Some(shape) = shape {
+        app = app.with_shape(shape);
+    }
+    if let Some(path) = args.ipc.clone() {
+        app = app.with_ipc(path);
+    }
+    if let Some(watcher) = theme_watcher {
+        app = app.with_theme_watcher(watcher);
+    }
+    if let Some(image) = image {
+        app = app.with_image(image, args.reveal, args.image_invert);
+    }
+    if !gradient.is_empty() {
+        let gradient = Gradient::evenly(&gradient).with_depth(depth);
+        app = app.with_gradient(gradient, args.gradient_along_path);
+    }
+    let app_result = app.run(terminal).await;
     ratatui::restore();
     app_result
 }
@@ -0,0 +1,344 @@
+use color_eyre::Result;
+use ratatui::{
+    buffer::Buffer,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Margin, Rect},
+    style::{Color, Stylize},
+    symbols::{self, border},
+    text::Line,
+    widgets::{canvas::Canvas, Block, Paragraph, Widget},
+    DefaultTerminal,
+};
+
+use crate::shapes::{Arc, ShapeSelect};
+
+/// Glyphs the shapes are drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum MarkerKind {
+    /// 2x4 dots per cell, needs a font with the braille block
+    Braille,
+    /// 1x2 pixels per cell with `▀` and `▄`
+    HalfBlock,
+    /// full cells `█`
+    Block,
+    /// `•`
+    Dot,
+    /// `#`, for terminals without unicode
+    Ascii,
+}
+
+impl MarkerKind {
+    pub const ALL: [MarkerKind; 5] = [
+        MarkerKind::Braille,
+        MarkerKind::HalfBlock,
+        MarkerKind::Block,
+        MarkerKind::Dot,
+        MarkerKind::Ascii,
+    ];
+
+    /// Canvas marker, ascii is drawn with blocks then replaced, see [`asciify`].
+    pub fn marker(self) -> symbols::Marker {
+        match self {
+            MarkerKind::Braille => symbols::Marker::Braille,
+            MarkerKind::HalfBlock => symbols::Marker::HalfBlock,
+            MarkerKind::Block | MarkerKind::Ascii => symbols::Marker::Block,
+            MarkerKind::Dot => symbols::Marker::Dot,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MarkerKind::Braille => "braille",
+            MarkerKind::HalfBlock => "half-block",
+            MarkerKind::Block => "block",
+            MarkerKind::Dot => "dot",
+            MarkerKind::Ascii => "ascii",
+        }
+    }
+
+    // next marker to try when this one is not supported
+    fn fallback(self) -> Self {
+        match self {
+            MarkerKind::Braille => MarkerKind::HalfBlock,
+            MarkerKind::HalfBlock | MarkerKind::Dot => MarkerKind::Block,
+            MarkerKind::Block | MarkerKind::Ascii => MarkerKind::Ascii,
+        }
+    }
+}
+
+impl From<symbols::Marker> for MarkerKind {
+    fn from(marker: symbols::Marker) -> Self {
+        match marker {
+            symbols::Marker::Braille => MarkerKind::Braille,
+            symbols::Marker::HalfBlock => MarkerKind::HalfBlock,
+            symbols::Marker::Dot => MarkerKind::Dot,
+            _ => MarkerKind::Block,
+        }
+    }
+}
+
+/// What the terminal can display, guessed from the environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TermCaps {
+    pub unicode: bool,
+    /// braille and other glyphs outside the few hundred of a console font
+    pub wide_font: bool,
+}
+
+impl Default for TermCaps {
+    fn default() -> Self {
+        Self {
+            unicode: true,
+            wide_font: true,
+        }
+    }
+}
+
+impl TermCaps {
+    pub fn from_env() -> Self {
+        Self::from_vars(|k| std::env::var(k).ok())
+    }
+
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let term = var("TERM").unwrap_or_default();
+        if term == "dumb" || term.starts_with("vt") {
+            return Self {
+                unicode: false,
+                wide_font: false,
+            };
+        }
+        // first locale variable set wins, like setlocale
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|k| var(k).filter(|v| !v.is_empty()));
+        let unicode = match locale {
+            Some(l) => {
+                let l = l.to_ascii_lowercase();
+                l.contains("utf-8") || l.contains("utf8")
+            }
+            None => true,
+        };
+        Self {
+            unicode,
+            // the linux console font has 512 glyphs at most, blocks but no braille
+            wide_font: unicode && term != "linux",
+        }
+    }
+
+    pub fn supports(&self, kind: MarkerKind) -> bool {
+        match kind {
+            MarkerKind::Ascii => true,
+            MarkerKind::Block | MarkerKind::HalfBlock => self.unicode,
+            MarkerKind::Braille | MarkerKind::Dot => self.wide_font,
+        }
+    }
+
+    /// `preferred` or the closest marker the terminal can show.
+    pub fn pick(&self, preferred: MarkerKind) -> MarkerKind {
+        let mut kind = preferred;
+        while !self.supports(kind) {
+            kind = kind.fallback();
+        }
+        kind
+    }
+}
+
+/// How markers are chosen: forced with `--marker`, else the shape's own marker
+/// downgraded to what the terminal supports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MarkerPolicy {
+    pub forced: Option<MarkerKind>,
+    pub caps: TermCaps,
+}
+
+impl MarkerPolicy {
+    pub fn pick(&self, preferred: symbols::Marker) -> MarkerKind {
+        self.forced
+            .unwrap_or_else(|| self.caps.pick(MarkerKind::from(preferred)))
+    }
+}
+
+pub const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+/// Replaces the non ascii glyphs of `area` with `#`.
+pub fn asciify(buf: &mut Buffer, area: Rect) {
+    for pos in area.positions() {
+        if let Some(cell) = buf.cell_mut(pos) {
+            if !cell.symbol().is_ascii() {
+                cell.set_char('#');
+            }
+        }
+    }
+}
+
+/// Renders `shape` with the given marker inside a bordered block.
+pub fn render_with(
+    kind: MarkerKind,
+    shape: &ShapeSelect,
+    title: Line,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let border = if kind == MarkerKind::Ascii {
+        ASCII_BORDER
+    } else {
+        border::PLAIN
+    };
+    let right = f64::from(area.width);
+    let top = f64::from(area.height).mul_add(2.0, -4.0);
+    Canvas::default()
+        .block(Block::bordered().border_set(border).title(title))
+        .marker(kind.marker())
+        .x_bounds([0.0, right])
+        .y_bounds([0.0, top])
+        .paint(|ctx| ctx.draw(shape))
+        .render(area, buf);
+    if kind == MarkerKind::Ascii {
+        asciify(buf, area.inner(Margin::new(1, 1)));
+    }
+}
+
+/// Diagnostic screen, the same arc drawn with every marker.
+pub struct Preview {
+    pub policy: MarkerPolicy,
+}
+
+impl Widget for &Preview {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let [header, grid] =
+            Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).areas(area);
+        let auto = self.policy.pick(Arc::get_marker());
+        let caps = self.policy.caps;
+        Paragraph::new(vec![
+            Line::from(format!(
+                "unicode: {}, wide font: {}, picked: {}",
+                caps.unicode,
+                caps.wide_font,
+                auto.name()
+            )),
+            Line::from("Pick the one that looks right with --marker <name>, q to quit"),
+        ])
+        .render(header, buf);
+
+        let cells = Layout::horizontal([Constraint::Fill(1); MarkerKind::ALL.len()]).split(grid);
+        for (kind, cell) in MarkerKind::ALL.into_iter().zip(cells.iter()) {
+            let right = f64::from(cell.width);
+            let top = f64::from(cell.height).mul_add(2.0, -4.0);
+            let shape = ShapeSelect::ArcSelect(Arc::new(8, Color::LightRed))
+                .center(right, top, 0.6)
+                .with_bgcolor(Color::DarkGray);
+            let title = if kind == auto {
+                Line::from(format!(" {} * ", kind.name())).bold()
+            } else {
+                Line::from(format!(" {} ", kind.name()))
+            };
+            render_with(kind, &shape, title, *cell, buf);
+        }
+    }
+}
+
+/// Shows the [`Preview`] until a key quits it.
+pub fn run_preview(mut terminal: DefaultTerminal, policy: MarkerPolicy) -> Result<()> {
+    let preview = Preview { policy };
+    loop {
+        terminal.draw(|frame| frame.render_widget(&preview, frame.area()))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press
+                && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+            {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn caps(vars: &[(&str, &str)]) -> TermCaps {
+        TermCaps::from_vars(|k| {
+            vars.iter()
+                .find(|(name, _)| *name == k)
+                .map(|(_, v)| v.to_string())
+        })
+    }
+
+    #[test]
+    fn when_detecting_should_read_term_and_locale() {
+        let full = caps(&[("TERM", "xterm-256color"), ("LANG", "en_US.UTF-8")]);
+        assert_eq!(TermCaps::default(), full);
+
+        let console = caps(&[("TERM", "linux"), ("LANG", "en_US.UTF-8")]);
+        assert!(console.unicode && !console.wide_font);
+
+        let c_locale = caps(&[("TERM", "xterm"), ("LC_ALL", "C"), ("LANG", "en_US.UTF-8")]);
+        assert!(!c_locale.unicode);
+
+        assert!(!caps(&[("TERM", "dumb")]).unicode);
+        assert!(caps(&[("TERM", "xterm")]).unicode);
+    }
+
+    #[test]
+    fn when_unsupported_should_fall_back() {
+        let console = TermCaps {
+            unicode: true,
+            wide_font: false,
+        };
+        assert_eq!(MarkerKind::HalfBlock, console.pick(MarkerKind::Braille));
+        assert_eq!(MarkerKind::Block, console.pick(MarkerKind::Dot));
+        let ascii = TermCaps {
+            unicode: false,
+            wide_font: false,
+        };
+        for kind in MarkerKind::ALL {
+            assert_eq!(MarkerKind::Ascii, ascii.pick(kind));
+            assert_eq!(kind, TermCaps::default().pick(kind));
+        }
+    }
+
+    #[test]
+    fn when_forced_should_ignore_caps() {
+        let policy = MarkerPolicy {
+            forced: Some(MarkerKind::Braille),
+            caps: TermCaps {
+                unicode: false,
+                wide_font: false,
+            },
+        };
+        assert_eq!(MarkerKind::Braille, policy.pick(symbols::Marker::Dot));
+    }
+
+    #[test]
+    fn when_ascii_should_only_render_ascii() {
+        let area = Rect::new(0, 0, 100, 20);
+        let mut buf = Buffer::empty(area);
+        let policy = MarkerPolicy {
+            forced: None,
+            caps: TermCaps {
+                unicode: false,
+                wide_font: false,
+            },
+        };
+        let shape = ShapeSelect::ArcSelect(Arc::new(8, Color::Red)).center(30.0, 36.0, 0.5);
+        render_with(
+            policy.pick(Arc::get_marker()),
+            &shape,
+            Line::from("a"),
+            area,
+            &mut buf,
+        );
+        assert!(area.positions().all(|p| buf[p].symbol().is_ascii()));
+        assert!(area.positions().any(|p| buf[p].symbol() == "#"));
+    }
+}
@@ -1,25 +1,33 @@
 use crate::spoty;
+use crate::theme;
+use futures::future::BoxFuture;
 use ratatui::{
     buffer::Buffer,
-    layout::{Constraint, Layout, Rect},
-    style::{
-        palette::tailwind::{BLUE, SLATE},
-        Color, Modifier, Style, Stylize,
-    },
+    layout::{Constraint, Layout, Position, Rect},
+    style::{Color, Stylize},
     symbols,
     text::Line,
     widgets::{
-        Block, Borders, HighlightSpacing, List, ListItem, ListState, Padding, Paragraph,
-        StatefulWidget, Widget, Wrap,
+        Block, Borders, HighlightSpacing, List, ListItem, ListState, Paragraph, StatefulWidget,
+        Widget,
     },
 };
-use rspotify::model::{PlayableItem, PrivateUser, SimplifiedPlaylist};
+use rspotify::model::{PlayableItem, PlaylistItem, PrivateUser, SimplifiedPlaylist};
+
+/// Result of a Spotify call made in the background.
+#[derive(Debug)]
+pub enum SpotifyResult {
+    Connected {
+        user: Option<PrivateUser>,
+        playlists: Option<Vec<SimplifiedPlaylist>>,
+    },
+    Tracks(Vec<PlaylistItem>),
+    Played,
+    Failed(String),
+}
 
-const TODO_HEADER_STYLE: Style = Style::new().fg(SLATE.c100).bg(BLUE.c800);
-const NORMAL_ROW_BG: Color = SLATE.c950;
-const ALT_ROW_BG_COLOR: Color = SLATE.c900;
-const SELECTED_STYLE: Style = Style::new().bg(SLATE.c800).add_modifier(Modifier::BOLD);
-const TEXT_FG_COLOR: Color = SLATE.c200;
+/// Spotify work to run in the background, see [`MusicPlayer::on_result`].
+pub type SpotifyTask = BoxFuture<'static, SpotifyResult>;
 
 ///TODO
 /// 1. select back from track to playlist with arrows
@@ -40,11 +48,15 @@ enum ActionType {
 }
 
 pub struct MusicPlayer {
-    spoty_api: spoty::SpotiApi,
+    spoty_api: Option<spoty::SpotiApi>, // created when first needed
+    settings: spoty::Settings,
     list_action: ActionList,
     user: Option<PrivateUser>,
     playlist: Option<Vec<SimplifiedPlaylist>>,
     state: ApiState,
+    theme: theme::Player,
+    list_area: Rect, // rows of the list in the last render, for the mouse
+    pending: bool,   // a task is running
 }
 struct ActionList {
     items: Vec<ActionItem>,
@@ -87,24 +99,19 @@ impl ActionItem {
 }
 
 impl MusicPlayer {
-    async fn connect(&mut self) -> ApiState {
-        let info = self.spoty_api.try_auth().await;
-        match info {
-            Err(e) => ApiState::Error(e.to_string()),
-            Ok(()) => ApiState::Connected("connection successful".to_string()),
-        }
-    }
-
     pub fn new() -> Self {
-        let spoty_api = spoty::SpotiApi::default();
         //TODO: if already connect maybe show the playlist
         let list_action_tuple = [("Connect to spotify", ActionType::ConnectToSpotify)];
         Self {
-            spoty_api,
+            spoty_api: None,
+            settings: spoty::Settings::default(),
             user: None,
             playlist: None,
             list_action: ActionList::from_iter(list_action_tuple),
             state: ApiState::default(),
+            theme: theme::DARK.player,
+            list_area: Rect::default(),
+            pending: false,
         }
     }
 
@@ -128,84 +135,127 @@ impl MusicPlayer {
         self.list_action.state.select_last();
     }
 
-    //TODO: not sure about the static life
-    pub async fn do_action(&mut self) {
-        if let Some(i) = self.list_action.state.selected() {
-            let r = &self.list_action.items[i].action_type;
-            //TODO: should be actionEnum diff from ApiState
-            match r {
-                ActionType::ConnectToSpotify => {
-                    let conn_res = self.connect().await;
-                    if let ApiState::Connected(_) = conn_res {
-                        self.user = self.spoty_api.get_user_info().await;
-                        self.playlist = self.spoty_api.get_user_playlist().await.ok();
-                        if let Some(l) = &self.playlist {
-                            let list_action_tuple: Vec<(String, ActionType)> = l
-                                .iter()
-                                .enumerate()
-                                .map(|(i, x)| (x.name.clone(), ActionType::Playlist(i)))
-                                .collect();
-                            self.list_action = ActionList::new(list_action_tuple.into_iter());
-                        }
-                    }
-                    self.state = conn_res;
-                }
+    pub fn select(&mut self, i: usize) {
+        self.list_action.state.select(Some(i));
+    }
 
-                ActionType::Playlist(i) => {
-                    if let Some(playlist) = &self.playlist {
-                        let p = playlist.get(*i).unwrap();
-                        //TODO:: remove the unwrap have early return
-                        let tracks = self.spoty_api.get_playlist_track(p).await.unwrap();
-                        let list_action_tuple: Vec<(String, ActionType)> = tracks
-                            .iter()
-                            .enumerate()
-                            .filter_map(|(i, x)| {
-                                if let Some(p) = &x.track {
-                                    match p {
-                                        PlayableItem::Track(t) => {
-                                            Some((t.name.clone(), ActionType::Track(i)))
-                                        }
-                                        _ => None,
-                                    }
-                                } else {
-                                    None
-                                }
-                            })
-                            .collect();
-                        self.list_action = ActionList::new(list_action_tuple.into_iter());
-                    }
-                }
+    pub fn selected(&self) -> Option<usize> {
+        self.list_action.state.selected()
+    }
 
-                ActionType::Track(_i) => {
-                    if let Some(playlist) = &self.playlist {
-                        let p = playlist.get(0).unwrap();
-                        self.spoty_api.play_music(p).await;
-                        //TODO:: remove the unwrap have early return
-                        // let tracks = self.spoty_api.get_playlist_track(p).await.unwrap();
-                        // let list_action_tuple: Vec<(String, ActionType)> = tracks
-                        //     .iter()
-                        //     .enumerate()
-                        //     .filter_map(|(i, x)| {
-                        //         if let Some(p) = &x.track {
-                        //             match p {
-                        //                 PlayableItem::Track(t) => {
-                        //                     Some((t.name.clone(), ActionType::Track(i)))
-                        //                 }
-                        //                 _ => None,
-                        //             }
-                        //         } else {
-                        //             None
-                        //         }
-                        //     })
-                        //     .collect();
-                        // self.list_action = ActionList::new(list_action_tuple.into_iter());
+    /// Index of the row drawn at `pos`, if any.
+    pub fn row_at(&self, pos: Position) -> Option<usize> {
+        if !self.list_area.contains(pos) {
+            return None;
+        }
+        let i = self.list_action.state.offset() + usize::from(pos.y - self.list_area.y);
+        (i < self.list_action.items.len()).then_some(i)
+    }
+
+    /// Connects to Spotify and fetches the playlists of the user, in a task
+    /// reporting to [`Self::on_result`]. `None` while a task is running.
+    pub fn connect_spotify(&mut self) -> Option<SpotifyTask> {
+        if self.pending {
+            return None;
+        }
+        self.pending = true;
+        let mut api = match self.api() {
+            Ok(api) => api,
+            Err(e) => return Some(failed(e.to_string())),
+        };
+        Some(Box::pin(async move {
+            if let Err(e) = api.try_auth().await {
+                return SpotifyResult::Failed(e.to_string());
+            }
+            let user = api.get_user_info().await;
+            let playlists = api.get_user_playlist().await.ok();
+            SpotifyResult::Connected { user, playlists }
+        }))
+    }
+
+    /// The work for the selected item, to run in a task reporting to [`Self::on_result`].
+    /// `None` while a task is running, its result would replace the list.
+    pub fn do_action(&mut self) -> Option<SpotifyTask> {
+        if self.pending {
+            return None;
+        }
+        let i = self.list_action.state.selected()?;
+        // playlists and tracks are only listed once connected
+        let api = self.spoty_api.clone();
+        //TODO: should be actionEnum diff from ApiState
+        let task: SpotifyTask = match self.list_action.items[i].action_type {
+            ActionType::ConnectToSpotify => return self.connect_spotify(),
+            ActionType::Playlist(i) => {
+                let playlist = self.playlist.as_ref()?.get(i)?.clone();
+                let api = api?;
+                Box::pin(async move {
+                    match api.get_playlist_track(&playlist).await {
+                        Ok(tracks) => SpotifyResult::Tracks(tracks),
+                        Err(e) => SpotifyResult::Failed(e.to_string()),
                     }
-                } // _ => {}
-            };
+                })
+            }
+            ActionType::Track(_i) => {
+                let playlist = self.playlist.as_ref()?.first()?.clone();
+                let api = api?;
+                Box::pin(async move {
+                    api.play_music(&playlist).await;
+                    SpotifyResult::Played
+                })
+            }
+        };
+        self.pending = true;
+        Some(task)
+    }
+
+    /// Applies what a task returned, with a message for the logs.
+    pub fn on_result(&mut self, result: SpotifyResult) -> Option<String> {
+        self.pending = false;
+        match result {
+            SpotifyResult::Connected { user, playlists } => {
+                self.user = user;
+                if let Some(l) = &playlists {
+                    let list_action_tuple: Vec<(String, ActionType)> = l
+                        .iter()
+                        .enumerate()
+                        .map(|(i, x)| (x.name.clone(), ActionType::Playlist(i)))
+                        .collect();
+                    self.list_action = ActionList::new(list_action_tuple);
+                }
+                self.playlist = playlists;
+                let message = "connection successful".to_string();
+                self.state = ApiState::Connected(message.clone());
+                Some(format!("spotify: {message}"))
+            }
+            SpotifyResult::Tracks(tracks) => {
+                let list_action_tuple: Vec<(String, ActionType)> = tracks
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, x)| match &x.track {
+                        Some(PlayableItem::Track(t)) => {
+                            Some((t.name.clone(), ActionType::Track(i)))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                self.list_action = ActionList::new(list_action_tuple);
+                None
+            }
+            SpotifyResult::Played => None,
+            SpotifyResult::Failed(e) => {
+                let message = format!("spotify: {e}");
+                self.state = ApiState::Error(e);
+                Some(message)
+            }
         }
     }
 }
 
+// a task that fails right away
+fn failed(message: String) -> SpotifyTask {
+    Box::pin(async move { SpotifyResult::Failed(message) })
+}
+
 impl FromIterator<(&'static str, ActionType)> for ActionList {
     fn from_iter<I: IntoIterator<Item = (&'static str, ActionType)>>(iter: I) -> Self {
         let items = iter
@@ -235,7 +285,7 @@ impl Widget for &mut MusicPlayer {
         // let [list_area, item_area] =
         //     Layout::vertical([Constraint::Fill(1), Constraint::Fill(1)]).areas(main_area);
 
-        MusicPlayer::render_header(header_area, buf);
+        MusicPlayer::render_header(header_area, buf, self.pending);
         MusicPlayer::render_footer(footer_area, buf);
         self.render_list(main_area, buf); //chagne this to an an info widg
                                           // self.render_selected_item(item_area, buf);
@@ -243,11 +293,40 @@ impl Widget for &mut MusicPlayer {
 }
 
 impl MusicPlayer {
-    fn render_header(area: Rect, buf: &mut Buffer) {
-        Paragraph::new("Actions")
-            .bold()
-            .centered()
-            .render(area, buf);
+    pub fn set_spotify(&mut self, settings: &spoty::Settings) {
+        self.settings = settings.clone();
+        self.spoty_api = None;
+    }
+
+    // the client id is only needed once Spotify is used
+    fn api(&mut self) -> color_eyre::Result<spoty::SpotiApi> {
+        if let Some(api) = &self.spoty_api {
+            return Ok(api.clone());
+        }
+        let api = spoty::SpotiApi::with_settings(&self.settings)?;
+        self.spoty_api = Some(api.clone());
+        Ok(api)
+    }
+
+    pub fn set_theme(&mut self, theme: theme::Player) {
+        self.theme = theme;
+    }
+
+    fn alternate_colors(&self, i: usize) -> Color {
+        if i.is_multiple_of(2) {
+            self.theme.row
+        } else {
+            self.theme.alt_row
+        }
+    }
+
+    fn render_header(area: Rect, buf: &mut Buffer, pending: bool) {
+        let title = if pending {
+            "Actions, loading…"
+        } else {
+            "Actions"
+        };
+        Paragraph::new(title).bold().centered().render(area, buf);
     }
 
     fn render_footer(area: Rect, buf: &mut Buffer) {
@@ -261,8 +340,9 @@ impl MusicPlayer {
             .title(Line::raw("Action List").centered())
             .borders(Borders::TOP)
             .border_set(symbols::border::EMPTY)
-            .border_style(TODO_HEADER_STYLE)
-            .bg(NORMAL_ROW_BG);
+            .border_style(self.theme.header)
+            .bg(self.theme.row);
+        self.list_area = block.inner(area);
 
         // Iterate through all elements in the `items` and stylize them.
         let items: Vec<ListItem> = self
@@ -271,15 +351,15 @@ impl MusicPlayer {
             .iter()
             .enumerate()
             .map(|(i, act_item)| {
-                let color = alternate_colors(i);
-                ListItem::from(act_item).bg(color)
+                let color = self.alternate_colors(i);
+                ListItem::from(act_item).fg(self.theme.text).bg(color)
             })
             .collect();
 
         // Create a List from all list items and highlight the currently selected one
         let list = List::new(items)
             .block(block)
-            .highlight_style(SELECTED_STYLE)
+            .highlight_style(self.theme.selected)
             .highlight_symbol(">")
             .highlight_spacing(HighlightSpacing::Always);
 
@@ -323,17 +403,9 @@ impl MusicPlayer {
     // }
 }
 
-const fn alternate_colors(i: usize) -> Color {
-    if i % 2 == 0 {
-        NORMAL_ROW_BG
-    } else {
-        ALT_ROW_BG_COLOR
-    }
-}
-
 impl From<&ActionItem> for ListItem<'_> {
     fn from(value: &ActionItem) -> Self {
-        let line = Line::styled(format!(" ☐ {}", value.action_name), TEXT_FG_COLOR);
+        let line = Line::raw(format!(" ☐ {}", value.action_name));
         ListItem::new(line)
     }
 }
@@ -475,47 +547,21 @@ impl From<&ActionItem> for ListItem<'_> {
 //     }
 // }
 
-// /// A new-type representing a string field with a label.
-// #[derive(Debug)]
-// struct StringField {
-//     label: &'static str,
-//     value: String,
-// }
+#[cfg(test)]
+mod test {
 
-// impl StringField {
-//     const fn new(label: &'static str) -> Self {
-//         Self {
-//             label,
-//             value: String::new(),
-//         }
-//     }
-
-//     /// Handle input events for the string input.
-//     fn on_key_press(&mut self, event: KeyEvent) {
-//         match event.code {
-//             KeyCode::Char(c) => self.value.push(c),
-//             KeyCode::Backspace => {
-//                 self.value.pop();
-//             }
-//             _ => {}
-//         }
-//     }
+    use super::*;
 
-//     fn cursor_offset(&self) -> Offset {
-//         let x = (self.label.len() + self.value.len() + 2) as i32;
-//         Offset::new(x, 0)
-//     }
-// }
+    #[test]
+    fn when_a_task_is_running_should_not_start_another() {
+        let mut player = MusicPlayer::default();
+        player.select_first();
+        // not awaited, nothing reaches Spotify
+        assert!(player.connect_spotify().is_some());
+        assert!(player.connect_spotify().is_none());
+        assert!(player.do_action().is_none());
 
-// impl Widget for &StringField {
-//     fn render(self, area: Rect, buf: &mut Buffer) {
-//         let constraints = [
-//             Constraint::Length(self.label.len() as u16 + 2),
-//             Constraint::Fill(1),
-//         ];
-//         let [label_area, value_area] = Layout::horizontal(constraints).areas(area);
-//         let label = Line::from_iter([self.label, ": "]).bold();
-//         label.render(label_area, buf);
-//         self.value.clone().render(value_area, buf);
-//     }
-// }
+        player.on_result(SpotifyResult::Failed("denied".to_string()));
+        assert!(player.do_action().is_some());
+    }
+}
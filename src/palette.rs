@@ -0,0 +1,321 @@
+use ratatui::{
+    buffer::Buffer,
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+    layout::{Constraint, Layout, Offset, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Clear, List, ListItem, ListState, StatefulWidget, Widget},
+};
+
+use crate::keymap::Action;
+use crate::prompt::StringField;
+use crate::theme;
+
+/// Commands kept for the recently used ordering.
+const RECENT: usize = 20;
+
+/// What the palette can run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Action(Action),
+    Shape(String),
+    Theme(String),
+    Preset(String),
+    ConnectSpotify,
+    Logs,
+}
+
+impl Command {
+    pub fn title(&self) -> String {
+        match self {
+            Command::Action(a) => a.description().to_string(),
+            Command::Shape(name) => format!("Change shape: {name}"),
+            Command::Theme(name) => format!("Switch theme: {name}"),
+            Command::Preset(name) => format!("Start preset: {name}"),
+            Command::ConnectSpotify => "Connect Spotify".to_string(),
+            Command::Logs => "Open logs".to_string(),
+        }
+    }
+}
+
+/// Outcome of a key press in the palette.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaletteEvent {
+    Editing,
+    Cancel,
+    Run(Command),
+}
+
+/// Score of `query` as a subsequence of `text`, ignoring case, `None` when it
+/// doesn't match. Consecutive letters and word starts score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0; // first character of `text` left to match
+    let mut last: Option<usize> = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let i = next + text[next..].iter().position(|c| *c == q)?;
+        score += match (last, i) {
+            (Some(l), _) if l + 1 == i => 8,
+            (_, 0) => 6,
+            _ if !text[i - 1].is_alphanumeric() => 6,
+            _ => 1,
+        };
+        // gaps cost a little, so that tighter matches win
+        score -= (i - next).min(4) as i64;
+        last = Some(i);
+        next = i + 1;
+    }
+    Some(score)
+}
+
+/// Fuzzy searchable list of commands, opened with Ctrl+p.
+#[derive(Debug)]
+pub struct Palette {
+    field: StringField,
+    commands: Vec<(Command, String)>, // with the keys bound to it
+    recent: Vec<Command>,             // most recent first
+    list: ListState,
+    theme: theme::Popup,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            field: StringField::new(">"),
+            commands: vec![],
+            recent: vec![],
+            list: ListState::default(),
+            theme: theme::DARK.popup,
+        }
+    }
+}
+
+impl Palette {
+    pub fn set_theme(&mut self, theme: theme::Popup) {
+        self.theme = theme;
+    }
+
+    /// Starts a new search among `commands`, each with the keys bound to it.
+    pub fn open(&mut self, commands: Vec<(Command, String)>) {
+        self.commands = commands;
+        self.field.set_value("");
+        self.list.select(Some(0));
+    }
+
+    pub fn recent(&self) -> &[Command] {
+        &self.recent
+    }
+
+    // rank in the recently used commands, the ones never used last
+    fn recency(&self, command: &Command) -> usize {
+        self.recent
+            .iter()
+            .position(|c| c == command)
+            .unwrap_or(RECENT)
+    }
+
+    /// Commands matching the query, best first, then most recently used.
+    pub fn matches(&self) -> Vec<&(Command, String)> {
+        let query = self.field.value();
+        let mut scored: Vec<(i64, usize, usize, &(Command, String))> = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| {
+                let score = fuzzy_score(query, &c.0.title())?;
+                Some((score, self.recency(&c.0), i, c))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+        scored.into_iter().map(|(.., c)| c).collect()
+    }
+
+    pub fn on_key_press(&mut self, event: KeyEvent) -> PaletteEvent {
+        let ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+        match event.code {
+            KeyCode::Esc => return PaletteEvent::Cancel,
+            KeyCode::Enter => {
+                let selected = self.list.selected().unwrap_or(0);
+                if let Some((command, _)) = self.matches().get(selected) {
+                    let command = command.clone();
+                    self.remember(&command);
+                    return PaletteEvent::Run(command);
+                }
+            }
+            KeyCode::Up => self.list.select_previous(),
+            KeyCode::Char('p') if ctrl => self.list.select_previous(),
+            KeyCode::Down => self.select_next(),
+            KeyCode::Char('n') if ctrl => self.select_next(),
+            _ => {
+                self.field.on_key_press(event);
+                self.list.select(Some(0));
+            }
+        }
+        PaletteEvent::Editing
+    }
+
+    // stops on the last match, the list state doesn't know the length
+    fn select_next(&mut self) {
+        let last = self.matches().len().saturating_sub(1);
+        let next = self.list.selected().map_or(0, |i| (i + 1).min(last));
+        self.list.select(Some(next));
+    }
+
+    fn remember(&mut self, command: &Command) {
+        self.recent.retain(|c| c != command);
+        self.recent.insert(0, command.clone());
+        self.recent.truncate(RECENT);
+    }
+
+    /// The popup over `area`, where the cursor goes.
+    pub fn popup(area: Rect) -> Rect {
+        let width = area.width.min(56);
+        let height = area.height.min(16);
+        Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + (area.height - height) / 4,
+            width,
+            height,
+        )
+    }
+
+    /// Where the terminal cursor goes, relative to the popup.
+    pub fn cursor_offset(&self) -> Offset {
+        let field = self.field.cursor_offset();
+        Offset {
+            x: field.x + 1,
+            y: field.y + 1,
+        }
+    }
+}
+
+impl Widget for &mut Palette {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::bordered()
+            .title(" Commands ")
+            .title_bottom(Line::from(" Enter run, Esc close ").right_aligned())
+            .style(self.theme.body);
+        let inner = block.inner(area);
+        Clear.render(area, buf);
+        block.render(area, buf);
+        let [field_area, list_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(inner);
+        self.field.render(field_area, buf);
+
+        let width = usize::from(list_area.width);
+        let items: Vec<ListItem> = self
+            .matches()
+            .into_iter()
+            .map(|(command, keys)| {
+                let title = command.title();
+                // keys on the right when they fit
+                let pad = width.saturating_sub(title.chars().count() + keys.chars().count() + 2);
+                ListItem::new(Line::from_iter([
+                    title.into(),
+                    " ".repeat(pad).into(),
+                    keys.clone().dim(),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .highlight_style(self.theme.selected)
+            .highlight_symbol(">");
+        StatefulWidget::render(list, list_area, buf, &mut self.list);
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn commands() -> Vec<(Command, String)> {
+        [
+            Command::Action(Action::Pause),
+            Command::Action(Action::Reset),
+            Command::Action(Action::MusicPlayer),
+            Command::Theme("solarized".to_string()),
+            Command::Preset("tea".to_string()),
+            Command::ConnectSpotify,
+        ]
+        .into_iter()
+        .map(|c| (c, String::new()))
+        .collect()
+    }
+
+    fn titles(p: &Palette) -> Vec<String> {
+        p.matches().iter().map(|(c, _)| c.title()).collect()
+    }
+
+    fn typed(p: &mut Palette, s: &str) {
+        for c in s.chars() {
+            p.on_key_press(KeyEvent::from(KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn when_scoring_should_prefer_tight_and_word_start_matches() {
+        assert_eq!(None, fuzzy_score("xyz", "Pause"));
+        assert_eq!(None, fuzzy_score("esuap", "Pause"));
+        assert!(fuzzy_score("", "Pause").is_some());
+        let tight = fuzzy_score("sol", "Switch theme: solarized").unwrap();
+        let loose = fuzzy_score("sol", "Start preset: school").unwrap();
+        assert!(tight > loose, "{tight} {loose}");
+        let start = fuzzy_score("mp", "Music player").unwrap();
+        let inside = fuzzy_score("mp", "Compact").unwrap();
+        assert!(start > inside, "{start} {inside}");
+    }
+
+    #[test]
+    fn when_typing_should_filter_and_run_the_selection() {
+        let mut p = Palette::default();
+        p.open(commands());
+        typed(&mut p, "music");
+        assert_eq!(vec!["Music player"], titles(&p));
+        assert_eq!(
+            PaletteEvent::Run(Command::Action(Action::MusicPlayer)),
+            p.on_key_press(KeyEvent::from(KeyCode::Enter))
+        );
+
+        p.open(commands());
+        typed(&mut p, "zzz");
+        assert!(titles(&p).is_empty());
+        assert_eq!(
+            PaletteEvent::Editing,
+            p.on_key_press(KeyEvent::from(KeyCode::Enter))
+        );
+        assert_eq!(
+            PaletteEvent::Cancel,
+            p.on_key_press(KeyEvent::from(KeyCode::Esc))
+        );
+    }
+
+    #[test]
+    fn when_used_should_list_recent_commands_first() {
+        let mut p = Palette::default();
+        let enter = KeyEvent::from(KeyCode::Enter);
+        for query in ["tea", "reset", "tea"] {
+            p.open(commands());
+            typed(&mut p, query);
+            p.on_key_press(enter);
+        }
+        assert_eq!(
+            [
+                Command::Preset("tea".to_string()),
+                Command::Action(Action::Reset)
+            ],
+            p.recent()
+        );
+        p.open(commands());
+        assert_eq!(
+            vec!["Start preset: tea", "Reset", "Pause"],
+            titles(&p)[..3].to_vec()
+        );
+        // down stops on the last match
+        for _ in 0..10 {
+            p.on_key_press(KeyEvent::from(KeyCode::Down));
+        }
+        assert_eq!(Some(commands().len() - 1), p.list.selected());
+    }
+}
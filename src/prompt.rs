@@ -0,0 +1,309 @@
+use std::time::Duration;
+
+use ratatui::{
+    buffer::Buffer,
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+    layout::{Constraint, Layout, Offset, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::Widget,
+};
+
+use crate::cli;
+use crate::theme;
+
+/// Entries kept for the recall with up and down.
+const HISTORY: usize = 50;
+
+/// A string field with a label.
+#[derive(Debug)]
+pub struct StringField {
+    label: &'static str,
+    value: String,
+}
+
+impl StringField {
+    pub const fn new(label: &'static str) -> Self {
+        Self {
+            label,
+            value: String::new(),
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn set_value(&mut self, value: &str) {
+        self.value = value.to_string();
+    }
+
+    /// Handle input events for the string input.
+    pub fn on_key_press(&mut self, event: KeyEvent) {
+        match event.code {
+            KeyCode::Char('u') if event.modifiers == KeyModifiers::CONTROL => self.value.clear(),
+            KeyCode::Char(c) if !event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.value.push(c)
+            }
+            KeyCode::Backspace => {
+                self.value.pop();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn cursor_offset(&self) -> Offset {
+        let x = (self.label.chars().count() + self.value.chars().count() + 2) as i32;
+        Offset { x, y: 0 }
+    }
+}
+
+impl Widget for &StringField {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let constraints = [
+            Constraint::Length(self.label.chars().count() as u16 + 2),
+            Constraint::Fill(1),
+        ];
+        let [label_area, value_area] = Layout::horizontal(constraints).areas(area);
+        let label = Line::from_iter([self.label, ": "]).bold();
+        label.render(label_area, buf);
+        self.value.as_str().render(value_area, buf);
+    }
+}
+
+/// What the prompt was given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entry {
+    Duration(Duration),
+    Preset(String),
+}
+
+/// Outcome of a key press in the prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptEvent {
+    Editing,
+    Cancel,
+    Submit(Entry),
+}
+
+/// Asks for a new duration such as `1h30m`, or the name of a preset.
+#[derive(Debug)]
+pub struct DurationPrompt {
+    field: StringField,
+    presets: Vec<String>,
+    history: Vec<String>,  // oldest first
+    recall: Option<usize>, // position in the history while browsing it
+    theme: theme::Popup,
+}
+
+impl Default for DurationPrompt {
+    fn default() -> Self {
+        Self::new(vec![])
+    }
+}
+
+impl DurationPrompt {
+    pub fn new(presets: Vec<String>) -> Self {
+        Self {
+            field: StringField::new("Duration"),
+            presets,
+            history: vec![],
+            recall: None,
+            theme: theme::DARK.popup,
+        }
+    }
+
+    pub fn set_theme(&mut self, theme: theme::Popup) {
+        self.theme = theme;
+    }
+
+    pub fn set_presets(&mut self, presets: Vec<String>) {
+        self.presets = presets;
+    }
+
+    /// Starts a new entry.
+    pub fn open(&mut self) {
+        self.field.set_value("");
+        self.recall = None;
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// The entry typed so far, or why it can't be used.
+    pub fn parse(&self) -> Result<Entry, &'static str> {
+        let value = self.field.value().trim();
+        if value.is_empty() {
+            return Err("type a duration like 1h30m or a preset name");
+        }
+        if self.presets.iter().any(|p| p == value) {
+            return Ok(Entry::Preset(value.to_string()));
+        }
+        match cli::parse_duration(value) {
+            Some(d) if !d.is_zero() => Ok(Entry::Duration(d)),
+            Some(_) => Err("the duration must be more than 0s"),
+            None => Err("not a duration (h, m, s units) nor a preset"),
+        }
+    }
+
+    pub fn on_key_press(&mut self, event: KeyEvent) -> PromptEvent {
+        match event.code {
+            KeyCode::Esc => return PromptEvent::Cancel,
+            KeyCode::Enter => {
+                if let Ok(entry) = self.parse() {
+                    self.remember();
+                    return PromptEvent::Submit(entry);
+                }
+            }
+            KeyCode::Up => self.recall_older(),
+            KeyCode::Down => self.recall_newer(),
+            _ => {
+                self.field.on_key_press(event);
+                self.recall = None;
+            }
+        }
+        PromptEvent::Editing
+    }
+
+    fn remember(&mut self) {
+        let value = self.field.value().trim().to_string();
+        if self.history.last() != Some(&value) {
+            self.history.push(value);
+        }
+        if self.history.len() > HISTORY {
+            self.history.remove(0);
+        }
+    }
+
+    fn recall_older(&mut self) {
+        let i = match self.recall {
+            Some(i) => i.saturating_sub(1),
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        self.recall = Some(i);
+        self.field.set_value(&self.history[i]);
+    }
+
+    fn recall_newer(&mut self) {
+        let Some(i) = self.recall else {
+            return;
+        };
+        if i + 1 < self.history.len() {
+            self.recall = Some(i + 1);
+            self.field.set_value(&self.history[i + 1]);
+        } else {
+            // past the newest entry, back to an empty line
+            self.recall = None;
+            self.field.set_value("");
+        }
+    }
+
+    /// Where the terminal cursor goes, relative to the area the prompt is drawn in.
+    pub fn cursor_offset(&self) -> Offset {
+        self.field.cursor_offset()
+    }
+}
+
+impl Widget for &DurationPrompt {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let feedback = match self.parse() {
+            Ok(Entry::Duration(d)) => {
+                Line::from(format!(" ✓ {} ", cli::format_duration(&d))).style(self.theme.ok)
+            }
+            Ok(Entry::Preset(p)) => Line::from(format!(" ✓ preset {p} ")).style(self.theme.ok),
+            Err(e) => Line::from(format!(" ✗ {e} ")).style(self.theme.error),
+        };
+        let [field_area, feedback_area] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Length(feedback.width() as u16),
+        ])
+        .areas(area);
+        buf.set_style(area, self.theme.body);
+        self.field.render(field_area, buf);
+        feedback.render(feedback_area, buf);
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn typed(prompt: &mut DurationPrompt, s: &str) -> PromptEvent {
+        prompt.open();
+        for c in s.chars() {
+            prompt.on_key_press(KeyEvent::from(KeyCode::Char(c)));
+        }
+        prompt.on_key_press(KeyEvent::from(KeyCode::Enter))
+    }
+
+    #[test]
+    fn when_typing_should_validate_durations_and_presets() {
+        let mut p = DurationPrompt::new(vec!["tea".to_string()]);
+        assert_eq!(
+            PromptEvent::Submit(Entry::Duration(Duration::from_secs(90 * 60))),
+            typed(&mut p, "1h30m")
+        );
+        assert_eq!(
+            PromptEvent::Submit(Entry::Preset("tea".to_string())),
+            typed(&mut p, "tea")
+        );
+        for bad in ["", "soon", "0s", "12"] {
+            assert_eq!(PromptEvent::Editing, typed(&mut p, bad), "{bad}");
+            assert!(p.parse().is_err(), "{bad}");
+        }
+        p.on_key_press(KeyEvent::from(KeyCode::Backspace));
+        assert_eq!("1", p.field.value());
+        p.on_key_press(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL));
+        assert_eq!("", p.field.value());
+        assert_eq!(
+            PromptEvent::Cancel,
+            p.on_key_press(KeyEvent::from(KeyCode::Esc))
+        );
+    }
+
+    #[test]
+    fn when_browsing_history_should_recall_entries() {
+        let mut p = DurationPrompt::default();
+        for s in ["1m", "2m", "2m", "3m"] {
+            typed(&mut p, s);
+        }
+        assert_eq!(["1m", "2m", "3m"], p.history());
+
+        p.open();
+        let up = KeyEvent::from(KeyCode::Up);
+        let down = KeyEvent::from(KeyCode::Down);
+        p.on_key_press(up);
+        assert_eq!("3m", p.field.value());
+        p.on_key_press(up);
+        p.on_key_press(up);
+        p.on_key_press(up);
+        assert_eq!("1m", p.field.value());
+        p.on_key_press(down);
+        assert_eq!("2m", p.field.value());
+        p.on_key_press(down);
+        p.on_key_press(down);
+        assert_eq!("", p.field.value());
+    }
+
+    #[test]
+    fn when_rendering_should_show_feedback() {
+        let mut p = DurationPrompt::default();
+        p.open();
+        for c in "1h30m".chars() {
+            p.on_key_press(KeyEvent::from(KeyCode::Char(c)));
+        }
+        let area = Rect::new(0, 0, 40, 1);
+        let mut buf = Buffer::empty(area);
+        p.render(area, &mut buf);
+        let text: String = area
+            .positions()
+            .map(|pos| buf[pos].symbol().to_string())
+            .collect();
+        assert!(text.starts_with("Duration: 1h30m"), "{text}");
+        assert!(text.contains("✓ 1h30m "), "{text}");
+        assert_eq!(Offset { x: 15, y: 0 }, p.cursor_offset());
+    }
+}
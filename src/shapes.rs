@@ -1,42 +1,350 @@
 use std::{
     f32::consts::PI,
     ops::{Div, Sub},
+    time::Duration,
 };
 
 use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
     style::Color,
     symbols,
     widgets::canvas::{Painter, Shape},
 };
 
+use crate::theme::{self, ColorDepth};
+
+mod custom;
+mod expr;
+mod fill;
+mod fuse;
+pub mod life;
+mod mask;
+pub mod particles;
+mod rain;
+pub use custom::Custom;
+pub use expr::Expr;
+pub use fill::{filled_count, Fill, FillPattern};
+pub use fuse::Fuse;
+pub use life::Life;
+pub use mask::{marker_resolution, Mask, Reveal};
+pub use rain::Rain;
+
 #[derive(Debug)]
 pub enum ShapeSelect {
     ArcSelect(Arc),
     SpiralSelect(Spiral),
-    ZigZagSelect(ZigZag),
+    FillSelect(Fill),
+    FuseSelect(Box<Fuse>),
+    RainSelect(Rain),
+    LifeSelect(Life),
+    MaskSelect(Mask),     // needs an image, never picked by `select_from`
+    CustomSelect(Custom), // loaded from the shapes file
 }
 
 impl ShapeSelect {
+    /// Number of shapes `select_from` can pick from.
+    pub const COUNT: u32 = 5 + FillPattern::ALL.len() as u32;
+
     pub fn select_from(select: u32, c: Color) -> Self {
         match select {
             0 => Self::ArcSelect(Arc::new(8, c)),
             1 => Self::SpiralSelect(Spiral::new(c)),
-            2 => Self::ZigZagSelect(ZigZag::new(5, c)),
+            2 => Self::FuseSelect(Box::new(Fuse::new(c, rand::random()))),
+            3 => Self::RainSelect(Rain::new(c, rand::random())),
+            4 => Self::LifeSelect(Life::new(c, rand::random())),
+            n if n < Self::COUNT => {
+                Self::FillSelect(Fill::new(FillPattern::ALL[n as usize - 5], c))
+            }
             _ => Self::SpiralSelect(Spiral::new(c)), //TODO: have something more smart here
         }
     }
+    /// Name of the shape `select_from` picks for `select`, without building it.
+    pub fn name_of(select: u32) -> &'static str {
+        match select {
+            0 => "arc",
+            1 => "spiral",
+            2 => "fuse",
+            3 => "rain",
+            4 => "life",
+            n if n < Self::COUNT => FillPattern::ALL[n as usize - 5].name(),
+            _ => "spiral",
+        }
+    }
+
+    /// Built-in shape with the given [`ShapeSelect::name`].
+    pub fn from_name(name: &str, c: Color) -> Option<Self> {
+        (0..Self::COUNT)
+            .find(|&i| Self::name_of(i) == name)
+            .map(|i| Self::select_from(i, c))
+    }
+
+    /// Names of the built-in shapes, then of the `custom` ones.
+    pub fn names(custom: &[Custom]) -> Vec<String> {
+        (0..Self::COUNT)
+            .map(|i| Self::name_of(i).to_string())
+            .chain(custom.iter().map(|c| c.name.clone()))
+            .collect()
+    }
+
+    /// Built-in or `custom` shape with the given [`ShapeSelect::name`].
+    pub fn find(name: &str, custom: &[Custom], c: Color) -> Option<Self> {
+        Self::from_name(name, c).or_else(|| {
+            custom
+                .iter()
+                .find(|s| s.name == name)
+                .map(|s| Self::CustomSelect(s.clone()))
+        })
+    }
+
+    /// Copy of the shape fitted to a `width` x `height` canvas at the given progress.
+    pub fn center(&self, width: f64, height: f64, perc: f64) -> Self {
+        match self {
+            ShapeSelect::ArcSelect(a) => {
+                ShapeSelect::ArcSelect(a.clone().center(width, height, perc))
+            }
+            ShapeSelect::SpiralSelect(s) => {
+                ShapeSelect::SpiralSelect(s.clone().center(width, height, perc))
+            }
+            ShapeSelect::FillSelect(f) => {
+                ShapeSelect::FillSelect(f.clone().center(width, height, perc))
+            }
+            ShapeSelect::FuseSelect(f) => {
+                ShapeSelect::FuseSelect(Box::new(f.as_ref().clone().center(width, height, perc)))
+            }
+            ShapeSelect::RainSelect(r) => ShapeSelect::RainSelect(r.clone().center(perc)),
+            ShapeSelect::LifeSelect(l) => ShapeSelect::LifeSelect(l.clone().center(perc)),
+            ShapeSelect::MaskSelect(m) => {
+                ShapeSelect::MaskSelect(m.clone().center(width, height, perc))
+            }
+            ShapeSelect::CustomSelect(c) => {
+                ShapeSelect::CustomSelect(c.clone().center(width, height, perc))
+            }
+        }
+    }
+    /// Shapes seeded from the timer name start from the same state for the same name.
+    pub fn with_label(self, label: &str) -> Self {
+        match self {
+            ShapeSelect::LifeSelect(l) => ShapeSelect::LifeSelect(l.with_label(label)),
+            s => s,
+        }
+    }
+    /// Advance shapes animated on their own, like particles. `area` is the last area
+    /// the shape was drawn in, borders excluded.
+    pub fn tick(&mut self, elapsed: Duration, perc: f64, area: Rect) {
+        match self {
+            ShapeSelect::FuseSelect(f) => f.tick(elapsed, perc),
+            ShapeSelect::RainSelect(r) => r.tick(elapsed),
+            ShapeSelect::LifeSelect(l) => l.tick(elapsed, area),
+            _ => {}
+        }
+    }
+    /// Short name, e.g. for the performance overlay and the benches.
+    pub fn name(&self) -> &str {
+        match self {
+            ShapeSelect::ArcSelect(_) => "arc",
+            ShapeSelect::SpiralSelect(_) => "spiral",
+            ShapeSelect::FillSelect(f) => f.pattern.name(),
+            ShapeSelect::FuseSelect(_) => "fuse",
+            ShapeSelect::RainSelect(_) => "rain",
+            ShapeSelect::LifeSelect(_) => "life",
+            ShapeSelect::MaskSelect(_) => "mask",
+            ShapeSelect::CustomSelect(c) => &c.name,
+        }
+    }
+
+    /// Shapes that move on their own, not only with the progress.
+    pub fn animated(&self) -> bool {
+        matches!(
+            self,
+            ShapeSelect::FuseSelect(_) | ShapeSelect::RainSelect(_) | ShapeSelect::LifeSelect(_)
+        )
+    }
+
+    /// Shapes drawn straight into the buffer instead of a canvas.
+    pub fn cell_renderer(&self) -> Option<&dyn CellRenderer> {
+        match self {
+            ShapeSelect::RainSelect(r) => Some(r),
+            ShapeSelect::LifeSelect(l) => Some(l),
+            _ => None,
+        }
+    }
     pub fn get_marker(&self) -> symbols::Marker {
         match self {
             ShapeSelect::ArcSelect(_) => Arc::get_marker(),
             ShapeSelect::SpiralSelect(_) => Spiral::get_marker(),
-            ShapeSelect::ZigZagSelect(_) => ZigZag::get_marker(),
+            ShapeSelect::FillSelect(_) => Fill::get_marker(),
+            ShapeSelect::FuseSelect(_) => Fuse::get_marker(),
+            // not drawn on a canvas
+            ShapeSelect::RainSelect(_) | ShapeSelect::LifeSelect(_) => symbols::Marker::Block,
+            ShapeSelect::MaskSelect(m) => m.get_marker(),
+            ShapeSelect::CustomSelect(_) => Custom::get_marker(),
         }
     }
+    /// Marker actually used to draw, only shapes sampling at the marker
+    /// resolution care.
+    pub fn with_marker(self, marker: symbols::Marker) -> Self {
+        match self {
+            ShapeSelect::MaskSelect(m) => ShapeSelect::MaskSelect(m.with_marker(marker)),
+            s => s,
+        }
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        match self {
+            ShapeSelect::ArcSelect(s) => s.color = color,
+            ShapeSelect::SpiralSelect(s) => s.color = color,
+            ShapeSelect::FillSelect(s) => s.color = color,
+            ShapeSelect::FuseSelect(s) => s.color = color,
+            ShapeSelect::RainSelect(s) => s.color = color,
+            ShapeSelect::LifeSelect(s) => s.color = color,
+            ShapeSelect::MaskSelect(s) => s.color = color,
+            ShapeSelect::CustomSelect(s) => s.color = color,
+        }
+    }
+
     pub fn with_bgcolor(self, bg: Color) -> Self {
         match self {
             ShapeSelect::ArcSelect(s) => ShapeSelect::ArcSelect(s.with_bgcolor(bg)),
             ShapeSelect::SpiralSelect(s) => ShapeSelect::SpiralSelect(s.with_gbcolor(bg)),
-            ShapeSelect::ZigZagSelect(s) => ShapeSelect::ZigZagSelect(s.with_gbcolor(bg)),
+            ShapeSelect::FillSelect(s) => ShapeSelect::FillSelect(s.with_gbcolor(bg)),
+            ShapeSelect::FuseSelect(s) => ShapeSelect::FuseSelect(Box::new(s.with_gbcolor(bg))),
+            ShapeSelect::RainSelect(s) => ShapeSelect::RainSelect(s.with_gbcolor(bg)),
+            ShapeSelect::LifeSelect(s) => ShapeSelect::LifeSelect(s.with_gbcolor(bg)),
+            ShapeSelect::MaskSelect(s) => ShapeSelect::MaskSelect(s.with_gbcolor(bg)),
+            ShapeSelect::CustomSelect(s) => ShapeSelect::CustomSelect(s.with_gbcolor(bg)),
+        }
+    }
+    /// `along_path` colours each drawn segment by its position on the path instead of
+    /// using a single colour for the current progress. Other shapes only support the latter.
+    pub fn with_gradient(self, gradient: Gradient, along_path: bool) -> Self {
+        match self {
+            ShapeSelect::ArcSelect(s) => {
+                ShapeSelect::ArcSelect(s.with_gradient(gradient, along_path))
+            }
+            ShapeSelect::SpiralSelect(s) => {
+                ShapeSelect::SpiralSelect(s.with_gradient(gradient, along_path))
+            }
+            ShapeSelect::CustomSelect(s) => {
+                ShapeSelect::CustomSelect(s.with_gradient(gradient, along_path))
+            }
+            ShapeSelect::FillSelect(s) => ShapeSelect::FillSelect(s.with_gradient(gradient)),
+            ShapeSelect::FuseSelect(s) => {
+                ShapeSelect::FuseSelect(Box::new(s.with_gradient(gradient)))
+            }
+            ShapeSelect::RainSelect(s) => ShapeSelect::RainSelect(s.with_gradient(gradient)),
+            ShapeSelect::LifeSelect(s) => ShapeSelect::LifeSelect(s.with_gradient(gradient)),
+            ShapeSelect::MaskSelect(s) => ShapeSelect::MaskSelect(s.with_gradient(gradient)),
+        }
+    }
+}
+
+/// Colour ramp sampled by a position in `0.0..=1.0`.
+///
+/// Stops are interpolated in RGB, then downsampled to the terminal [`ColorDepth`].
+/// A stop without a known RGB value (`Color::Reset`) is used as is up to the next stop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    stops: Vec<(f64, Color)>,
+    depth: ColorDepth,
+}
+
+impl Gradient {
+    pub fn new(stops: Vec<(f64, Color)>) -> Self {
+        let mut stops: Vec<(f64, Color)> = stops
+            .into_iter()
+            .map(|(p, c)| (p.clamp(0.0, 1.0), c))
+            .collect();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self {
+            stops,
+            depth: ColorDepth::TrueColor,
+        }
+    }
+
+    /// Stops spread evenly from 0 to 1.
+    pub fn evenly(colors: &[Color]) -> Self {
+        let last = colors.len().saturating_sub(1).max(1) as f64;
+        Self::new(
+            colors
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (i as f64 / last, *c))
+                .collect(),
+        )
+    }
+
+    /// green → amber → red
+    pub fn traffic_light() -> Self {
+        Self::evenly(&[theme::LIGHT_GREEN, theme::AMBER, theme::LIGHT_RED])
+    }
+
+    pub fn with_depth(mut self, depth: ColorDepth) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn at(&self, t: f64) -> Color {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let c = match self.stops.iter().position(|(p, _)| *p >= t) {
+            None => self.stops.last().map_or(Color::Reset, |(_, c)| *c),
+            Some(0) => self.stops[0].1,
+            Some(i) => {
+                let (p0, c0) = self.stops[i - 1];
+                let (p1, c1) = self.stops[i];
+                match (theme::to_rgb(c0), theme::to_rgb(c1)) {
+                    (Some(a), Some(b)) if p1 > p0 => lerp_rgb(a, b, (t - p0) / (p1 - p0)),
+                    _ => c0,
+                }
+            }
+        };
+        self.depth.downsample(c)
+    }
+}
+
+fn lerp_rgb((r0, g0, b0): (u8, u8, u8), (r1, g1, b1): (u8, u8, u8), t: f64) -> Color {
+    let lerp = |a: u8, b: u8| f64::from(a).mul_add(1.0 - t, f64::from(b) * t).round() as u8;
+    Color::Rgb(lerp(r0, r1), lerp(g0, g1), lerp(b0, b1))
+}
+
+impl ShapeSelect {
+    /// How the path is painted, `None` for the shapes that draw more than their path.
+    pub fn look(&self) -> Option<TracedLook<'_>> {
+        match self {
+            ShapeSelect::ArcSelect(a) => Some(a.look()),
+            ShapeSelect::SpiralSelect(s) => Some(s.look()),
+            ShapeSelect::FillSelect(f) => Some(f.look()),
+            ShapeSelect::MaskSelect(m) => Some(m.look()),
+            ShapeSelect::CustomSelect(c) => Some(c.look()),
+            ShapeSelect::FuseSelect(_)
+            | ShapeSelect::RainSelect(_)
+            | ShapeSelect::LifeSelect(_) => None,
+        }
+    }
+}
+
+impl Traced for ShapeSelect {
+    fn trace(&self, painter: &mut Painter<'_, '_>) -> Vec<(usize, usize)> {
+        match self {
+            ShapeSelect::ArcSelect(a) => a.trace(painter),
+            ShapeSelect::SpiralSelect(s) => s.trace(painter),
+            ShapeSelect::FillSelect(f) => f.trace(painter),
+            ShapeSelect::FuseSelect(f) => f.trace(painter),
+            ShapeSelect::MaskSelect(m) => m.trace(painter),
+            ShapeSelect::CustomSelect(c) => c.trace(painter),
+            ShapeSelect::RainSelect(_) | ShapeSelect::LifeSelect(_) => vec![],
+        }
+    }
+
+    fn draw_traced(&self, painter: &mut Painter<'_, '_>, cells: &[(usize, usize)]) {
+        match self {
+            ShapeSelect::ArcSelect(a) => a.draw_traced(painter, cells),
+            ShapeSelect::SpiralSelect(s) => s.draw_traced(painter, cells),
+            ShapeSelect::FillSelect(f) => f.draw_traced(painter, cells),
+            ShapeSelect::FuseSelect(f) => f.draw_traced(painter, cells),
+            ShapeSelect::MaskSelect(m) => m.draw_traced(painter, cells),
+            ShapeSelect::CustomSelect(c) => c.draw_traced(painter, cells),
+            ShapeSelect::RainSelect(_) | ShapeSelect::LifeSelect(_) => {}
         }
     }
 }
@@ -46,7 +354,11 @@ impl Shape for ShapeSelect {
         match self {
             ShapeSelect::ArcSelect(a) => a.draw(painter),
             ShapeSelect::SpiralSelect(s) => s.draw(painter),
-            ShapeSelect::ZigZagSelect(z) => z.draw(painter),
+            ShapeSelect::FillSelect(f) => f.draw(painter),
+            ShapeSelect::FuseSelect(f) => f.draw(painter),
+            ShapeSelect::MaskSelect(m) => m.draw(painter),
+            ShapeSelect::CustomSelect(c) => c.draw(painter),
+            ShapeSelect::RainSelect(_) | ShapeSelect::LifeSelect(_) => {}
         }
     }
 }
@@ -60,6 +372,8 @@ pub struct Arc {
     pub arc_perc: f64, // percentage arc 0 to 100
     pub color: Color,
     pub bgcolor: Option<Color>,
+    pub gradient: Option<Gradient>,
+    pub along_path: bool,
 }
 
 impl Arc {
@@ -72,6 +386,8 @@ impl Arc {
             arc_perc: 0.0,
             color,
             bgcolor: None,
+            gradient: None,
+            along_path: false,
         }
     }
     pub fn with_bgcolor(mut self, bg: Color) -> Self {
@@ -79,6 +395,21 @@ impl Arc {
         self
     }
 
+    pub fn with_gradient(mut self, gradient: Gradient, along_path: bool) -> Self {
+        self.gradient = Some(gradient);
+        self.along_path = along_path;
+        self
+    }
+
+    pub fn look(&self) -> TracedLook<'_> {
+        TracedLook {
+            perc: self.arc_perc,
+            color: self.color,
+            bgcolor: self.bgcolor,
+            along: self.gradient.as_ref().filter(|_| self.along_path),
+        }
+    }
+
     pub fn get_marker() -> symbols::Marker {
         symbols::Marker::Dot
     }
@@ -90,171 +421,38 @@ impl Arc {
             radius: width.min(height).div(2.0),
             thickness: self.thickness,
             arc_perc,
-            color: self.color,
+            color: progress_color(&self.gradient, self.color, arc_perc),
             bgcolor: self.bgcolor,
+            gradient: self.gradient,
+            along_path: self.along_path,
         }
     }
 }
 
-impl Shape for Arc {
-    fn draw(&self, painter: &mut Painter<'_, '_>) {
-        if let Some(c) = self.bgcolor {
+impl Traced for Arc {
+    fn trace(&self, painter: &mut Painter<'_, '_>) -> Vec<(usize, usize)> {
+        let mut path = PathBuilder::default();
+        for angle in 0..360 {
+            let radians = f64::from(angle).to_radians();
             for t in 0..self.thickness {
                 let radius = self.radius.sub(t as f64);
-                for angle in 0..360 {
-                    let radians = f64::from(angle).to_radians();
-                    let circle_x = radius.mul_add(radians.cos(), self.x);
-                    let circle_y = radius.mul_add(radians.sin(), self.y);
-                    if let Some((x, y)) = painter.get_point(circle_x, circle_y) {
-                        painter.paint(x, y, c);
-                    }
-                }
-            }
-        }
-        let arc_completion = (self.arc_perc) * 360.0;
-        let arc_completion = arc_completion as u32;
-        for t in 0..self.thickness {
-            let radius = self.radius.sub(t as f64);
-            for angle in 0..arc_completion {
-                let radians = f64::from(angle).to_radians();
                 let circle_x = radius.mul_add(radians.cos(), self.x);
                 let circle_y = radius.mul_add(radians.sin(), self.y);
-                if let Some((x, y)) = painter.get_point(circle_x, circle_y) {
-                    painter.paint(x, y, self.color);
-                }
+                path.push(painter.get_point(circle_x, circle_y));
             }
         }
+        path.cells
     }
-}
-
-#[derive(Debug, Default, Clone, PartialEq)]
-pub struct ZigZag {
-    pub x: f64,
-    pub y: f64,
-    pub size: f64,
-    pub gap: usize,
-    pub fill_perc: f64, // this is a percentage
-    pub color: Color,
-    pub bgcolor: Option<Color>,
-}
-
-impl ZigZag {
-    pub fn new(gap: usize, color: Color) -> Self {
-        Self {
-            x: 0.0,
-            y: 0.0,
-            size: 0.0,
-            gap,
-            fill_perc: 0.0,
-            color,
-            bgcolor: None,
-        }
-    }
-    pub fn get_marker() -> symbols::Marker {
-        symbols::Marker::HalfBlock
-    }
-
-    pub fn with_gbcolor(mut self, bg: Color) -> Self {
-        self.bgcolor = Some(bg);
-        self
-    }
-
-    pub fn center(self, width: f64, height: f64, fill_perc: f64) -> Self {
-        let size = width.min(height);
-        let x = width.div(2.0) - size.div(2.0);
-        let x = x.max(0.0);
-        let y = height.div(2.0) - size.div(2.0);
-        let y = y.max(0.0);
 
-        Self {
-            x,
-            y,
-            size,
-            gap: self.gap,
-            fill_perc,
-            color: self.color,
-            bgcolor: self.bgcolor,
-        }
-    }
-
-    fn drawfill(&self, painter: &mut Painter<'_, '_>, fill_per: f64, c: Color) {
-        let fill_limit = (fill_per * (self.size + self.gap as f64).powi(2)) as usize;
-        let mut filled = 0;
-
-        // rust 101 for the noob! filled and painter variable are owned by this
-        // closure now that's why you got error! .... just pure pain
-        let mut draw_point = |x, y, inc| {
-            filled += inc;
-            if filled > fill_limit {
-                return false;
-            }
-            if let Some((zx, zy)) =
-                painter.get_point((x + self.x as usize) as f64, (y + self.y as usize) as f64)
-            {
-                painter.paint(zx, zy, c);
-            }
-            true
-        };
-
-        let size = self.size as usize;
-        //let offset_x = self.x as usize;
-
-        let mut going_up = true;
-        for d in (0..size).step_by(self.gap) {
-            let mut dy = d;
-            going_up = !going_up;
-            for dx in 0..d {
-                let (x, y) = if going_up { (d - dx, dx) } else { (dx, dy) };
-                if !draw_point(x, y, self.gap) {
-                    return;
-                }
-
-                dy = dy.saturating_sub(1);
-            }
-            for g in 1..self.gap {
-                let (x, y) = if going_up { (0, d + g) } else { (d + g, 0) };
-                if !draw_point(x, y, 1) {
-                    return;
-                }
-            }
-        }
-
-        for d in (0..size).step_by(self.gap) {
-            let mut dy = size;
-            going_up = !going_up;
-            for dx in d..(size) {
-                let (x, y) = if going_up {
-                    (size - (dx - d), dx)
-                } else {
-                    (dx, dy)
-                };
-                if !draw_point(x, y, self.gap) {
-                    return;
-                }
-
-                dy = dy.saturating_sub(1);
-            }
-
-            for g in 1..self.gap {
-                let (x, y) = if going_up {
-                    (d + g, size)
-                } else {
-                    (size, d + g)
-                };
-                if !draw_point(x, y, self.gap) {
-                    return;
-                }
-            }
-        }
+    fn draw_traced(&self, painter: &mut Painter<'_, '_>, cells: &[(usize, usize)]) {
+        self.look().paint(painter, cells);
     }
 }
 
-impl Shape for ZigZag {
+impl Shape for Arc {
     fn draw(&self, painter: &mut Painter<'_, '_>) {
-        if let Some(c) = self.bgcolor {
-            self.drawfill(painter, 1.0, c);
-        }
-        self.drawfill(painter, self.fill_perc, self.color);
+        let cells = self.trace(painter);
+        self.draw_traced(painter, &cells);
     }
 }
 
@@ -266,6 +464,8 @@ pub struct Spiral {
     pub completion_perc: f64, // percentage arc 0 to 100
     pub color: Color,
     pub bgcolor: Option<Color>,
+    pub gradient: Option<Gradient>,
+    pub along_path: bool,
 }
 
 impl Spiral {
@@ -277,6 +477,8 @@ impl Spiral {
             completion_perc: 0.0,
             color,
             bgcolor: None,
+            gradient: None,
+            along_path: false,
         }
     }
 
@@ -286,8 +488,10 @@ impl Spiral {
             y: height.div(2.0),
             radius: width.min(height).div(2.0),
             completion_perc: arc_perc,
-            color: self.color,
+            color: progress_color(&self.gradient, self.color, arc_perc),
             bgcolor: self.bgcolor,
+            gradient: self.gradient,
+            along_path: self.along_path,
         }
     }
 
@@ -296,17 +500,35 @@ impl Spiral {
         self
     }
 
+    pub fn with_gradient(mut self, gradient: Gradient, along_path: bool) -> Self {
+        self.gradient = Some(gradient);
+        self.along_path = along_path;
+        self
+    }
+
+    pub fn look(&self) -> TracedLook<'_> {
+        TracedLook {
+            perc: self.completion_perc,
+            color: self.color,
+            bgcolor: self.bgcolor,
+            along: self.gradient.as_ref().filter(|_| self.along_path),
+        }
+    }
+
     pub fn get_marker() -> symbols::Marker {
         symbols::Marker::HalfBlock
     }
+}
 
-    fn drawfill(&self, painter: &mut Painter<'_, '_>, fill: f64, c: Color) {
+impl Traced for Spiral {
+    fn trace(&self, painter: &mut Painter<'_, '_>) -> Vec<(usize, usize)> {
         // Archimedean spiral: r =  a + b*theta
 
         let a = -self.radius;
         let b = 1.0;
         let range = -a / (b * PI as f64);
-        let range = (180.0 * range * fill) as u32;
+        let range = (180.0 * range) as u32;
+        let mut path = PathBuilder::default();
         for angle in 0..range {
             let radians = f64::from(angle).to_radians();
             let radius = a + (b * radians);
@@ -314,19 +536,178 @@ impl Spiral {
             //convert to x,y coordinate
             let circle_x = radius.mul_add(radians.cos(), self.x);
             let circle_y = radius.mul_add(radians.sin(), self.y);
-            if let Some((x, y)) = painter.get_point(circle_x, circle_y) {
-                painter.paint(x, y, c);
-            }
+            path.push(painter.get_point(circle_x, circle_y));
         }
+        path.cells
+    }
+
+    fn draw_traced(&self, painter: &mut Painter<'_, '_>, cells: &[(usize, usize)]) {
+        self.look().paint(painter, cells);
     }
 }
 
 impl Shape for Spiral {
     fn draw(&self, painter: &mut Painter<'_, '_>) {
+        let cells = self.trace(painter);
+        self.draw_traced(painter, &cells);
+    }
+}
+
+/// Animations drawn straight into the terminal cells instead of through a `Canvas`,
+/// for text based effects. `area` excludes the border.
+pub trait CellRenderer {
+    fn render_cells(&self, area: Rect, buf: &mut Buffer);
+}
+
+/// Shapes drawn as the prefix of a fixed path of grid cells, the prefix follows the
+/// progress. The path only depends on the canvas size so it can be computed once and
+/// reused for every frame.
+pub trait Traced {
+    /// Every cell of the full path in drawing order, without duplicates.
+    fn trace(&self, painter: &mut Painter<'_, '_>) -> Vec<(usize, usize)>;
+
+    /// Paint the progress over `cells` previously returned by `trace`.
+    fn draw_traced(&self, painter: &mut Painter<'_, '_>, cells: &[(usize, usize)]);
+}
+
+// collects grid cells in order, skipping the ones outside the canvas or already seen
+#[derive(Default)]
+struct PathBuilder {
+    cells: Vec<(usize, usize)>,
+    seen: std::collections::HashSet<(usize, usize)>,
+}
+
+impl PathBuilder {
+    fn push(&mut self, cell: Option<(usize, usize)>) {
+        if let Some(cell) = cell {
+            if self.seen.insert(cell) {
+                self.cells.push(cell);
+            }
+        }
+    }
+}
+
+/// How a [`Traced`] shape paints its path: the progress prefix in the foreground,
+/// the rest in background, each cell once.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TracedLook<'a> {
+    pub perc: f64,
+    pub color: Color,
+    pub bgcolor: Option<Color>,
+    /// colours the prefix along the path instead of `color`
+    pub along: Option<&'a Gradient>,
+}
+
+impl TracedLook<'_> {
+    /// Number of cells of a `len` long path in the foreground.
+    pub fn filled(&self, len: usize) -> usize {
+        filled_count(len, self.perc)
+    }
+
+    pub fn paint(&self, painter: &mut Painter<'_, '_>, cells: &[(usize, usize)]) {
+        self.paint_where(painter, cells, (0, 0), |_| true);
+    }
+
+    /// Paints the cells for which `keep` holds, moved back by `origin`, in the same
+    /// order as [`TracedLook::paint`] so that cells sharing a marker end up the same.
+    pub fn paint_where(
+        &self,
+        painter: &mut Painter<'_, '_>,
+        cells: &[(usize, usize)],
+        origin: (usize, usize),
+        keep: impl Fn((usize, usize)) -> bool,
+    ) {
+        let (fg, bg) = cells.split_at(self.filled(cells.len()));
+        let mut paint = |(x, y): (usize, usize), c: Color| {
+            if keep((x, y)) {
+                painter.paint(x - origin.0, y - origin.1, c);
+            }
+        };
         if let Some(c) = self.bgcolor {
-            self.drawfill(painter, 1.0, c);
+            for &cell in bg {
+                paint(cell, c);
+            }
+        }
+        let len = cells.len() as f64;
+        for (i, &cell) in fg.iter().enumerate() {
+            paint(
+                cell,
+                self.along.map_or(self.color, |g| g.at(i as f64 / len)),
+            );
         }
+    }
+}
+
+fn progress_color(gradient: &Option<Gradient>, color: Color, perc: f64) -> Color {
+    gradient.as_ref().map_or(color, |g| g.at(perc))
+}
+
+/// splitmix64, cheap and good enough to scatter cells and seed boards.
+pub(crate) fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod test {
 
-        self.drawfill(painter, self.completion_perc, self.color);
+    use super::*;
+
+    #[test]
+    fn when_gradient_at_stop_should_return_stop_color() {
+        let g = Gradient::evenly(&[Color::Rgb(0, 0, 0), Color::Rgb(200, 100, 0)]);
+        assert_eq!(Color::Rgb(0, 0, 0), g.at(0.0));
+        assert_eq!(Color::Rgb(200, 100, 0), g.at(1.0));
+    }
+
+    #[test]
+    fn when_gradient_between_stops_should_interpolate_rgb() {
+        let g = Gradient::evenly(&[Color::Rgb(0, 0, 0), Color::Rgb(200, 100, 0)]);
+        assert_eq!(Color::Rgb(100, 50, 0), g.at(0.5));
+    }
+
+    #[test]
+    fn when_gradient_out_of_range_should_clamp() {
+        let g = Gradient::traffic_light();
+        assert_eq!(g.at(0.0), g.at(-3.0));
+        assert_eq!(g.at(1.0), g.at(42.0));
+    }
+
+    #[test]
+    fn when_gradient_on_256_colors_should_return_indexed() {
+        let g = Gradient::traffic_light().with_depth(ColorDepth::Ansi256);
+        assert!(matches!(g.at(0.3), Color::Indexed(_)));
+    }
+
+    #[test]
+    fn when_gradient_on_16_colors_should_return_base_color() {
+        let g = Gradient::evenly(&[Color::Rgb(0, 250, 0), Color::Rgb(250, 0, 0)])
+            .with_depth(ColorDepth::Ansi16);
+        assert_eq!(Color::LightGreen, g.at(0.0));
+        assert_eq!(Color::LightRed, g.at(1.0));
+    }
+
+    #[test]
+    fn when_listing_names_should_find_every_shape_back() {
+        let heart = Custom::new("heart", vec![(0.0, 0.0), (1.0, 1.0)], Color::Red).unwrap();
+        let custom = [heart];
+        for i in 0..ShapeSelect::COUNT {
+            let shape = ShapeSelect::select_from(i, Color::Red);
+            assert_eq!(ShapeSelect::name_of(i), shape.name());
+        }
+        let names = ShapeSelect::names(&custom);
+        assert_eq!(ShapeSelect::COUNT as usize + 1, names.len());
+        assert_eq!(Some("heart"), names.last().map(String::as_str));
+        assert!(names.iter().any(|n| n == "arc") && names.iter().any(|n| n == "spiral"));
+        for name in &names {
+            let shape = ShapeSelect::find(name, &custom, Color::Red);
+            assert_eq!(
+                Some(name.as_str()),
+                shape.as_ref().map(|s| s.name()),
+                "{name}"
+            );
+        }
     }
 }
@@ -0,0 +1,448 @@
+use std::{f64::consts::TAU, ops::Div, path::Path, sync::Arc};
+
+use color_eyre::{eyre::eyre, Result};
+use ratatui::{
+    style::Color,
+    symbols,
+    widgets::canvas::{Painter, Shape},
+};
+use serde::Deserialize;
+
+use super::expr::Expr;
+use super::{progress_color, Gradient, PathBuilder, Traced, TracedLook};
+
+// a shapes file is a list of `[[shape]]` tables
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ShapesFile {
+    #[serde(default)]
+    shape: Vec<ShapeDef>,
+}
+
+// the same file untyped, to check the keys of each shape
+#[derive(Debug, Deserialize)]
+struct RawShapesFile {
+    #[serde(default)]
+    shape: Vec<toml::Table>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShapeDef {
+    name: String,
+    /// points computed along the curve, the cells in between are interpolated
+    #[serde(default = "default_samples")]
+    samples: usize,
+    #[serde(flatten)]
+    curve: CurveDef,
+}
+
+fn default_samples() -> usize {
+    720
+}
+
+/// More would only slow the loading down, the cells are interpolated anyway.
+const MAX_SAMPLES: usize = 100_000;
+
+// keys of every shape, next to the ones of its kind
+const SHAPE_KEYS: [&str; 3] = ["name", "samples", "kind"];
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase", deny_unknown_fields)]
+enum CurveDef {
+    /// `r(theta)`
+    Polar {
+        r: String,
+        #[serde(default = "full_turn")]
+        theta: [Bound; 2],
+    },
+    /// `x(t)`, `y(t)`
+    Parametric {
+        x: String,
+        y: String,
+        #[serde(default = "full_turn")]
+        t: [Bound; 2],
+    },
+    /// straight segments through the points
+    Polyline {
+        points: Vec<[f64; 2]>,
+        #[serde(default)]
+        closed: bool,
+    },
+}
+
+/// Range bound, a number or a constant expression such as `"2 * pi"`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum Bound {
+    Num(f64),
+    Expr(String),
+}
+
+impl Bound {
+    fn value(&self) -> Result<f64> {
+        match self {
+            Bound::Num(n) => Ok(*n),
+            Bound::Expr(s) => Ok(Expr::parse(s, &[])?.eval(&[])),
+        }
+    }
+}
+
+impl CurveDef {
+    /// Keys of a `kind`, none when the kind is unknown.
+    fn keys(kind: &str) -> Option<&'static [&'static str]> {
+        match kind {
+            "polar" => Some(&["r", "theta"]),
+            "parametric" => Some(&["x", "y", "t"]),
+            "polyline" => Some(&["points", "closed"]),
+            _ => None,
+        }
+    }
+}
+
+// serde reports unknown keys without the name of the shape
+fn check_keys(table: &toml::Table) -> Result<()> {
+    let name = table.get("name").and_then(|n| n.as_str()).unwrap_or("?");
+    let kind = table.get("kind").and_then(|k| k.as_str()).unwrap_or("");
+    // an unknown kind is left to serde
+    let Some(keys) = CurveDef::keys(kind) else {
+        return Ok(());
+    };
+    let known = |k: &str| SHAPE_KEYS.contains(&k) || keys.contains(&k);
+    match table.keys().find(|k| !known(k)) {
+        Some(key) => Err(eyre!("shape `{name}`: unknown field `{key}`")),
+        None => Ok(()),
+    }
+}
+
+fn full_turn() -> [Bound; 2] {
+    [Bound::Num(0.0), Bound::Num(TAU)]
+}
+
+// `samples` values of `t` evenly spread over the range, ends included
+fn sweep(range: &[Bound; 2], samples: usize) -> Result<impl Iterator<Item = f64>> {
+    let (from, to) = (range[0].value()?, range[1].value()?);
+    let steps = samples.max(2) - 1;
+    Ok((0..=steps).map(move |i| from + (to - from) * i as f64 / steps as f64))
+}
+
+impl ShapeDef {
+    fn check_samples(&self) -> Result<()> {
+        if self.samples > MAX_SAMPLES {
+            return Err(eyre!(
+                "{} samples, at most {MAX_SAMPLES} are allowed",
+                self.samples
+            ));
+        }
+        Ok(())
+    }
+
+    fn points(&self) -> Result<Vec<(f64, f64)>> {
+        let points = match &self.curve {
+            CurveDef::Polar { r, theta } => {
+                let r = Expr::parse(r, &["theta"])?;
+                sweep(theta, self.samples)?
+                    .map(|a| {
+                        let r = r.eval(&[a]);
+                        (r * a.cos(), r * a.sin())
+                    })
+                    .collect()
+            }
+            CurveDef::Parametric { x, y, t } => {
+                let (x, y) = (Expr::parse(x, &["t"])?, Expr::parse(y, &["t"])?);
+                sweep(t, self.samples)?
+                    .map(|t| (x.eval(&[t]), y.eval(&[t])))
+                    .collect()
+            }
+            CurveDef::Polyline { points, closed } => {
+                let mut p: Vec<(f64, f64)> = points.iter().map(|p| (p[0], p[1])).collect();
+                if *closed {
+                    p.extend(p.first().copied());
+                }
+                p
+            }
+        };
+        Ok(points)
+    }
+}
+
+/// A user defined curve, drawn progressively from its first to its last point.
+///
+/// The points are normalized once when loading: centered on the origin and
+/// scaled so the curve fits in `-1.0..=1.0` on both axes, aspect ratio kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Custom {
+    pub name: String,
+    pub x: f64,
+    pub y: f64,
+    pub radius: f64,
+    pub completion_perc: f64,
+    pub color: Color,
+    pub bgcolor: Option<Color>,
+    pub gradient: Option<Gradient>,
+    pub along_path: bool,
+    points: Arc<Vec<(f64, f64)>>,
+}
+
+impl Custom {
+    pub fn new(name: &str, points: Vec<(f64, f64)>, color: Color) -> Result<Self> {
+        let finite: Vec<(f64, f64)> = points
+            .into_iter()
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .collect();
+        if finite.len() < 2 {
+            return Err(eyre!("shape `{name}` has less than 2 drawable points"));
+        }
+        let (min_x, max_x, min_y, max_y) = finite.iter().fold(
+            (f64::MAX, f64::MIN, f64::MAX, f64::MIN),
+            |(a, b, c, d), &(x, y)| (a.min(x), b.max(x), c.min(y), d.max(y)),
+        );
+        let (cx, cy) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+        let half = (max_x - min_x).max(max_y - min_y) / 2.0;
+        let half = if half > 0.0 { half } else { 1.0 };
+        let normalized = finite
+            .iter()
+            .map(|(x, y)| ((x - cx) / half, (y - cy) / half))
+            .collect();
+        Ok(Self {
+            name: name.to_string(),
+            x: 0.0,
+            y: 0.0,
+            radius: 0.0,
+            completion_perc: 0.0,
+            color,
+            bgcolor: None,
+            gradient: None,
+            along_path: false,
+            points: Arc::new(normalized),
+        })
+    }
+
+    /// Parses a shapes file, each `[[shape]]` table is one shape:
+    ///
+    /// ```toml
+    /// [[shape]]
+    /// name = "spiral"
+    /// kind = "polar"
+    /// r = "theta"
+    /// theta = [0, "6 * pi"]
+    ///
+    /// [[shape]]
+    /// name = "heart"
+    /// kind = "parametric"
+    /// x = "16 * sin(t)^3"
+    /// y = "13 * cos(t) - 5 * cos(2 * t) - 2 * cos(3 * t) - cos(4 * t)"
+    /// t = [0, "tau"]
+    ///
+    /// [[shape]]
+    /// name = "bolt"
+    /// kind = "polyline"
+    /// points = [[0, 10], [6, 4], [3, 4], [8, -6], [2, 0], [5, 0]]
+    /// ```
+    pub fn parse_all(src: &str, color: Color) -> Result<Vec<Self>> {
+        let raw: RawShapesFile = toml::from_str(src)?;
+        raw.shape.iter().try_for_each(check_keys)?;
+        let file: ShapesFile = toml::from_str(src)?;
+        file.shape
+            .iter()
+            .map(|def| {
+                let points = def
+                    .check_samples()
+                    .and_then(|()| def.points())
+                    .map_err(|e| eyre!("shape `{}`: {e}", def.name))?;
+                Self::new(&def.name, points, color)
+            })
+            .collect()
+    }
+
+    pub fn load_all(path: &Path, color: Color) -> Result<Vec<Self>> {
+        let src = std::fs::read_to_string(path).map_err(|e| eyre!("{}: {e}", path.display()))?;
+        Self::parse_all(&src, color).map_err(|e| eyre!("{}: {e}", path.display()))
+    }
+
+    pub fn points(&self) -> &[(f64, f64)] {
+        &self.points
+    }
+
+    pub fn look(&self) -> TracedLook<'_> {
+        TracedLook {
+            perc: self.completion_perc,
+            color: self.color,
+            bgcolor: self.bgcolor,
+            along: self.gradient.as_ref().filter(|_| self.along_path),
+        }
+    }
+
+    pub fn get_marker() -> symbols::Marker {
+        symbols::Marker::HalfBlock
+    }
+
+    pub fn with_gbcolor(mut self, bg: Color) -> Self {
+        self.bgcolor = Some(bg);
+        self
+    }
+
+    pub fn with_gradient(mut self, gradient: Gradient, along_path: bool) -> Self {
+        self.gradient = Some(gradient);
+        self.along_path = along_path;
+        self
+    }
+
+    pub fn center(self, width: f64, height: f64, arc_perc: f64) -> Self {
+        Self {
+            x: width.div(2.0),
+            y: height.div(2.0),
+            radius: width.min(height).div(2.0),
+            completion_perc: arc_perc,
+            color: progress_color(&self.gradient, self.color, arc_perc),
+            ..self
+        }
+    }
+}
+
+impl Traced for Custom {
+    fn trace(&self, painter: &mut Painter<'_, '_>) -> Vec<(usize, usize)> {
+        let to_canvas = |(x, y): (f64, f64)| {
+            (
+                x.mul_add(self.radius, self.x),
+                y.mul_add(self.radius, self.y),
+            )
+        };
+        let mut path = PathBuilder::default();
+        for pair in self.points.windows(2) {
+            let (from, to) = (to_canvas(pair[0]), to_canvas(pair[1]));
+            // two steps per canvas unit is enough for the finest marker
+            let len = (to.0 - from.0).hypot(to.1 - from.1);
+            let steps = (len * 2.0).ceil().max(1.0) as u32;
+            for i in 0..steps {
+                let k = f64::from(i) / f64::from(steps);
+                path.push(painter.get_point(
+                    (to.0 - from.0).mul_add(k, from.0),
+                    (to.1 - from.1).mul_add(k, from.1),
+                ));
+            }
+        }
+        if let Some(&last) = self.points.last() {
+            let (x, y) = to_canvas(last);
+            path.push(painter.get_point(x, y));
+        }
+        path.cells
+    }
+
+    fn draw_traced(&self, painter: &mut Painter<'_, '_>, cells: &[(usize, usize)]) {
+        self.look().paint(painter, cells);
+    }
+}
+
+impl Shape for Custom {
+    fn draw(&self, painter: &mut Painter<'_, '_>) {
+        let cells = self.trace(painter);
+        self.draw_traced(painter, &cells);
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ratatui::widgets::canvas::Context;
+
+    use super::*;
+
+    const SHAPES: &str = r#"
+        [[shape]]
+        name = "spiral"
+        kind = "polar"
+        r = "theta"
+        theta = [0, "6 * pi"]
+
+        [[shape]]
+        name = "heart"
+        kind = "parametric"
+        x = "16 * sin(t)^3"
+        y = "13 * cos(t) - 5 * cos(2 * t) - 2 * cos(3 * t) - cos(4 * t)"
+
+        [[shape]]
+        name = "square"
+        kind = "polyline"
+        points = [[0, 0], [2, 0], [2, 2], [0, 2]]
+        closed = true
+    "#;
+
+    #[test]
+    fn when_parsing_should_normalize_every_kind() {
+        let shapes = Custom::parse_all(SHAPES, Color::Red).unwrap();
+        let names: Vec<_> = shapes.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(vec!["spiral", "heart", "square"], names);
+        for s in &shapes {
+            assert!(s
+                .points()
+                .iter()
+                .all(|(x, y)| x.abs() <= 1.0 + 1e-9 && y.abs() <= 1.0 + 1e-9));
+        }
+        let square = shapes[2].points();
+        assert_eq!(
+            &[
+                (-1.0, -1.0),
+                (1.0, -1.0),
+                (1.0, 1.0),
+                (-1.0, 1.0),
+                (-1.0, -1.0)
+            ],
+            square
+        );
+    }
+
+    #[test]
+    fn when_invalid_should_name_the_shape() {
+        let err = Custom::parse_all(
+            "[[shape]]\nname = \"bad\"\nkind = \"polar\"\nr = \"t\"",
+            Color::Red,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("shape `bad`"), "{err}");
+        assert!(err.contains("unknown variable `t`"), "{err}");
+
+        let err = Custom::parse_all("[[shape]]\nname = \"x\"\nkind = \"blob\"", Color::Red);
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn when_misspelled_or_too_fine_should_name_the_shape() {
+        let polar = "[[shape]]\nname = \"ring\"\nkind = \"polar\"\nr = \"1\"\n";
+        let parse = |extra: &str| {
+            Custom::parse_all(&format!("{polar}{extra}"), Color::Red).map_err(|e| e.to_string())
+        };
+        assert!(parse("samples = 2000").is_ok());
+        let err = parse("sample = 2000").unwrap_err();
+        assert!(
+            err.contains("shape `ring`: unknown field `sample`"),
+            "{err}"
+        );
+        // a key of another kind
+        let err = parse("points = [[0, 0], [1, 1]]").unwrap_err();
+        assert!(err.contains("unknown field `points`"), "{err}");
+        let err = parse("samples = 1000000000").unwrap_err();
+        assert!(err.contains("shape `ring`: 1000000000 samples"), "{err}");
+    }
+
+    #[test]
+    fn when_traced_should_be_contiguous() {
+        let shapes = Custom::parse_all(SHAPES, Color::Red).unwrap();
+        for s in shapes {
+            let s = s.center(40.0, 40.0, 0.0);
+            let mut ctx = Context::new(40, 20, [0.0, 40.0], [0.0, 40.0], Custom::get_marker());
+            let cells = s.trace(&mut Painter::from(&mut ctx));
+            assert!(cells.len() > 20, "{}", s.name);
+            // each cell touches one already traced, the progress never jumps
+            for (i, &(x, y)) in cells.iter().enumerate().skip(1) {
+                assert!(
+                    cells[..i]
+                        .iter()
+                        .any(|&(px, py)| px.abs_diff(x) <= 1 && py.abs_diff(y) <= 1),
+                    "{} jumps at {i}",
+                    s.name
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use std::f64::consts::{E, PI, TAU};
+
+use color_eyre::{eyre::eyre, Result};
+
+/// Arithmetic expression over a few named variables, e.g. `1 + 0.5 * sin(3 * theta)`.
+///
+/// Supports `+ - * / % ^`, parentheses, the constants `pi`, `tau`, `e` and the
+/// usual math functions. Variables are resolved when parsing so evaluating is
+/// just walking the tree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Num(f64),
+    Var(usize),
+    Neg(Box<Expr>),
+    Bin(Op, Box<Expr>, Box<Expr>),
+    Call(Func, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Func {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Atan2,
+    Sqrt,
+    Abs,
+    Exp,
+    Ln,
+    Floor,
+    Ceil,
+    Min,
+    Max,
+}
+
+impl Func {
+    fn from_name(name: &str) -> Option<(Self, usize)> {
+        let f = match name {
+            "sin" => (Func::Sin, 1),
+            "cos" => (Func::Cos, 1),
+            "tan" => (Func::Tan, 1),
+            "asin" => (Func::Asin, 1),
+            "acos" => (Func::Acos, 1),
+            "atan" => (Func::Atan, 1),
+            "atan2" => (Func::Atan2, 2),
+            "sqrt" => (Func::Sqrt, 1),
+            "abs" => (Func::Abs, 1),
+            "exp" => (Func::Exp, 1),
+            "ln" => (Func::Ln, 1),
+            "floor" => (Func::Floor, 1),
+            "ceil" => (Func::Ceil, 1),
+            "min" => (Func::Min, 2),
+            "max" => (Func::Max, 2),
+            _ => return None,
+        };
+        Some(f)
+    }
+
+    fn apply(self, a: &[f64]) -> f64 {
+        match self {
+            Func::Sin => a[0].sin(),
+            Func::Cos => a[0].cos(),
+            Func::Tan => a[0].tan(),
+            Func::Asin => a[0].asin(),
+            Func::Acos => a[0].acos(),
+            Func::Atan => a[0].atan(),
+            Func::Atan2 => a[0].atan2(a[1]),
+            Func::Sqrt => a[0].sqrt(),
+            Func::Abs => a[0].abs(),
+            Func::Exp => a[0].exp(),
+            Func::Ln => a[0].ln(),
+            Func::Floor => a[0].floor(),
+            Func::Ceil => a[0].ceil(),
+            Func::Min => a[0].min(a[1]),
+            Func::Max => a[0].max(a[1]),
+        }
+    }
+}
+
+impl Expr {
+    /// Parses `src`, `vars` are the variable names allowed in it, in the order
+    /// their values are given to [`Expr::eval`].
+    pub fn parse(src: &str, vars: &[&str]) -> Result<Self> {
+        let mut p = Parser { src, pos: 0, vars };
+        let e = p.expr()?;
+        p.skip_blank();
+        if p.pos < src.len() {
+            return Err(p.error("unexpected input"));
+        }
+        Ok(e)
+    }
+
+    pub fn eval(&self, values: &[f64]) -> f64 {
+        match self {
+            Expr::Num(n) => *n,
+            Expr::Var(i) => values[*i],
+            Expr::Neg(e) => -e.eval(values),
+            Expr::Bin(op, a, b) => {
+                let (a, b) = (a.eval(values), b.eval(values));
+                match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div => a / b,
+                    Op::Rem => a.rem_euclid(b),
+                    Op::Pow => a.powf(b),
+                }
+            }
+            Expr::Call(f, args) => {
+                let args: Vec<f64> = args.iter().map(|a| a.eval(values)).collect();
+                f.apply(&args)
+            }
+        }
+    }
+}
+
+// recursive descent, one function per precedence level
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    vars: &'a [&'a str],
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: &str) -> color_eyre::Report {
+        eyre!("{msg} at column {} in `{}`", self.pos + 1, self.src)
+    }
+
+    fn skip_blank(&mut self) {
+        let rest = &self.src[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_blank();
+        self.src[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    // expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<Expr> {
+        let mut e = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Some('+') => Op::Add,
+                Some('-') => Op::Sub,
+                _ => return Ok(e),
+            };
+            self.pos += 1;
+            e = Expr::Bin(op, Box::new(e), Box::new(self.term()?));
+        }
+    }
+
+    // term := unary (('*' | '/' | '%') unary)*
+    fn term(&mut self) -> Result<Expr> {
+        let mut e = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some('*') => Op::Mul,
+                Some('/') => Op::Div,
+                Some('%') => Op::Rem,
+                _ => return Ok(e),
+            };
+            self.pos += 1;
+            e = Expr::Bin(op, Box::new(e), Box::new(self.unary()?));
+        }
+    }
+
+    // unary := '-' unary | '+' unary | power
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat('-') {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        if self.eat('+') {
+            return self.unary();
+        }
+        self.power()
+    }
+
+    // power := atom ('^' unary)?, right associative and tighter than unary minus
+    fn power(&mut self) -> Result<Expr> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            return Ok(Expr::Bin(Op::Pow, Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let e = self.expr()?;
+                if !self.eat(')') {
+                    return Err(self.error("expected `)`"));
+                }
+                Ok(e)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => self.ident(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Expr> {
+        let start = self.pos;
+        let rest = &self.src[start..];
+        let mut len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        // exponent, `1e-3`
+        let after = &rest[len..];
+        if after.starts_with(['e', 'E']) {
+            let sign = usize::from(after[1..].starts_with(['+', '-']));
+            let digits = after[1 + sign..]
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(after.len() - 1 - sign);
+            if digits > 0 {
+                len += 1 + sign + digits;
+            }
+        }
+        self.pos += len;
+        rest[..len]
+            .parse()
+            .map(Expr::Num)
+            .map_err(|_| self.error("invalid number"))
+    }
+
+    fn ident(&mut self) -> Result<Expr> {
+        let start = self.pos;
+        let rest = &self.src[start..];
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let name = &rest[..len];
+        self.pos += len;
+
+        if self.eat('(') {
+            let Some((func, arity)) = Func::from_name(name) else {
+                self.pos = start;
+                return Err(self.error(&format!("unknown function `{name}`")));
+            };
+            let mut args = vec![self.expr()?];
+            while self.eat(',') {
+                args.push(self.expr()?);
+            }
+            if !self.eat(')') {
+                return Err(self.error("expected `)`"));
+            }
+            if args.len() != arity {
+                self.pos = start;
+                return Err(self.error(&format!("`{name}` takes {arity} argument(s)")));
+            }
+            return Ok(Expr::Call(func, args));
+        }
+
+        match name {
+            "pi" => Ok(Expr::Num(PI)),
+            "tau" => Ok(Expr::Num(TAU)),
+            "e" => Ok(Expr::Num(E)),
+            _ => match self.vars.iter().position(|v| *v == name) {
+                Some(i) => Ok(Expr::Var(i)),
+                None => {
+                    self.pos = start;
+                    Err(self.error(&format!("unknown variable `{name}`")))
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn eval(src: &str) -> f64 {
+        Expr::parse(src, &["t"]).unwrap().eval(&[2.0])
+    }
+
+    #[test]
+    fn when_operators_should_follow_precedence() {
+        assert_eq!(7.0, eval("1 + 2 * 3"));
+        assert_eq!(9.0, eval("(1 + 2) * 3"));
+        assert_eq!(-4.0, eval("-2^2"));
+        assert_eq!(512.0, eval("2^3^2"));
+        assert_eq!(1.0, eval("7 % 3"));
+        assert_eq!(0.5, eval("1 / t"));
+        assert_eq!(0.002, eval("2e-3"));
+    }
+
+    #[test]
+    fn when_functions_and_constants_should_evaluate() {
+        assert!((eval("sin(pi / 2)") - 1.0).abs() < 1e-12);
+        assert!((eval("cos(tau)") - 1.0).abs() < 1e-12);
+        assert_eq!(3.0, eval("max(t, 3)"));
+        assert_eq!(2.0, eval("sqrt(abs(-4))"));
+    }
+
+    #[test]
+    fn when_invalid_should_report_position() {
+        for (src, msg) in [
+            ("1 +", "unexpected end"),
+            ("(1", "expected `)`"),
+            ("foo(1)", "unknown function `foo`"),
+            ("theta", "unknown variable `theta`"),
+            ("min(1)", "takes 2 argument"),
+            ("1 2", "unexpected input"),
+        ] {
+            let err = Expr::parse(src, &["t"]).unwrap_err().to_string();
+            assert!(err.contains(msg), "{src}: {err}");
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use ratatui::{
+    style::Color,
+    symbols,
+    widgets::canvas::{Painter, Shape},
+};
+
+use super::{progress_color, Gradient, Traced, TracedLook};
+
+/// Order in which the cells of the canvas get filled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FillPattern {
+    /// diagonals, alternating direction
+    #[default]
+    ZigZag,
+    /// generalized Hilbert curve, works on any rectangle
+    Hilbert,
+    /// rows, alternating direction
+    Snake,
+    /// rows, always left to right
+    Rows,
+}
+
+impl FillPattern {
+    pub const ALL: [FillPattern; 4] = [Self::ZigZag, Self::Hilbert, Self::Snake, Self::Rows];
+
+    /// Name of the fill shape, e.g. in the config.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ZigZag => "fill-zigzag",
+            Self::Hilbert => "fill-hilbert",
+            Self::Snake => "fill-snake",
+            Self::Rows => "fill-rows",
+        }
+    }
+
+    /// Every cell of a `width` x `height` grid exactly once, in fill order.
+    pub fn cells(self, width: usize, height: usize) -> Vec<(usize, usize)> {
+        let mut cells = Vec::with_capacity(width * height);
+        if width == 0 || height == 0 {
+            return cells;
+        }
+        match self {
+            Self::ZigZag => {
+                for d in 0..(width + height - 1) {
+                    let first = d.saturating_sub(height - 1);
+                    let last = d.min(width - 1);
+                    if d % 2 == 0 {
+                        cells.extend((first..=last).map(|x| (x, d - x)));
+                    } else {
+                        cells.extend((first..=last).rev().map(|x| (x, d - x)));
+                    }
+                }
+            }
+            Self::Hilbert => {
+                let (w, h) = (width as i64, height as i64);
+                if w >= h {
+                    gilbert(&mut cells, (0, 0), (w, 0), (0, h));
+                } else {
+                    gilbert(&mut cells, (0, 0), (0, h), (w, 0));
+                }
+            }
+            Self::Snake => {
+                for y in 0..height {
+                    if y % 2 == 0 {
+                        cells.extend((0..width).map(|x| (x, y)));
+                    } else {
+                        cells.extend((0..width).rev().map(|x| (x, y)));
+                    }
+                }
+            }
+            Self::Rows => {
+                for y in 0..height {
+                    cells.extend((0..width).map(|x| (x, y)));
+                }
+            }
+        }
+        cells
+    }
+}
+
+// Generalized Hilbert curve ("gilbert") by Jakub Červený: walks the rectangle spanned by
+// the `a` (major) and `b` (minor) axis vectors starting at `p`.
+fn gilbert(cells: &mut Vec<(usize, usize)>, p: (i64, i64), a: (i64, i64), b: (i64, i64)) {
+    let (x, y) = p;
+    let (ax, ay) = a;
+    let (bx, by) = b;
+    let w = (ax + ay).abs();
+    let h = (bx + by).abs();
+    let (dax, day) = (ax.signum(), ay.signum());
+    let (dbx, dby) = (bx.signum(), by.signum());
+
+    if h == 1 {
+        cells.extend((0..w).map(|i| ((x + i * dax) as usize, (y + i * day) as usize)));
+        return;
+    }
+    if w == 1 {
+        cells.extend((0..h).map(|i| ((x + i * dbx) as usize, (y + i * dby) as usize)));
+        return;
+    }
+
+    let (mut ax2, mut ay2) = (ax.div_euclid(2), ay.div_euclid(2));
+    let (mut bx2, mut by2) = (bx.div_euclid(2), by.div_euclid(2));
+    let w2 = (ax2 + ay2).abs();
+    let h2 = (bx2 + by2).abs();
+
+    if 2 * w > 3 * h {
+        if w2 % 2 == 1 && w > 2 {
+            ax2 += dax;
+            ay2 += day;
+        }
+        gilbert(cells, (x, y), (ax2, ay2), (bx, by));
+        gilbert(cells, (x + ax2, y + ay2), (ax - ax2, ay - ay2), (bx, by));
+    } else {
+        if h2 % 2 == 1 && h > 2 {
+            bx2 += dbx;
+            by2 += dby;
+        }
+        gilbert(cells, (x, y), (bx2, by2), (ax2, ay2));
+        gilbert(cells, (x + bx2, y + by2), (ax, ay), (bx - bx2, by - by2));
+        gilbert(
+            cells,
+            (x + (ax - dax) + (bx2 - dbx), y + (ay - day) + (by2 - dby)),
+            (-bx2, -by2),
+            (-(ax - ax2), -(ay - ay2)),
+        );
+    }
+}
+
+/// Number of cells to paint out of `total` for a progress in `0.0..=1.0`.
+pub fn filled_count(total: usize, perc: f64) -> usize {
+    let perc = if perc.is_nan() {
+        0.0
+    } else {
+        perc.clamp(0.0, 1.0)
+    };
+    (perc * total as f64).round() as usize
+}
+
+/// Fills the whole canvas, cell by cell, following a [`FillPattern`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Fill {
+    pub width: f64,
+    pub height: f64,
+    pub pattern: FillPattern,
+    pub fill_perc: f64, // this is a percentage
+    pub color: Color,
+    pub bgcolor: Option<Color>,
+    pub gradient: Option<Gradient>,
+}
+
+impl Fill {
+    pub fn new(pattern: FillPattern, color: Color) -> Self {
+        Self {
+            width: 0.0,
+            height: 0.0,
+            pattern,
+            fill_perc: 0.0,
+            color,
+            bgcolor: None,
+            gradient: None,
+        }
+    }
+
+    pub fn look(&self) -> TracedLook<'_> {
+        TracedLook {
+            perc: self.fill_perc,
+            color: self.color,
+            bgcolor: self.bgcolor,
+            along: None,
+        }
+    }
+
+    pub fn get_marker() -> symbols::Marker {
+        symbols::Marker::HalfBlock
+    }
+
+    pub fn with_gbcolor(mut self, bg: Color) -> Self {
+        self.bgcolor = Some(bg);
+        self
+    }
+
+    pub fn with_gradient(mut self, gradient: Gradient) -> Self {
+        self.gradient = Some(gradient);
+        self
+    }
+
+    pub fn center(self, width: f64, height: f64, fill_perc: f64) -> Self {
+        Self {
+            width,
+            height,
+            fill_perc,
+            color: progress_color(&self.gradient, self.color, fill_perc),
+            ..self
+        }
+    }
+}
+
+impl Traced for Fill {
+    fn trace(&self, painter: &mut Painter<'_, '_>) -> Vec<(usize, usize)> {
+        // bottom right corner of the canvas gives the grid resolution
+        let Some((max_x, max_y)) = painter.get_point(self.width, 0.0) else {
+            return vec![];
+        };
+        // start from the bottom left, the painter grid starts at the top
+        self.pattern
+            .cells(max_x + 1, max_y + 1)
+            .into_iter()
+            .map(|(x, y)| (x, max_y - y))
+            .collect()
+    }
+
+    fn draw_traced(&self, painter: &mut Painter<'_, '_>, cells: &[(usize, usize)]) {
+        self.look().paint(painter, cells);
+    }
+}
+
+impl Shape for Fill {
+    fn draw(&self, painter: &mut Painter<'_, '_>) {
+        let cells = self.trace(painter);
+        self.draw_traced(painter, &cells);
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use ratatui::{buffer::Buffer, layout::Rect, widgets::canvas::Canvas, widgets::Widget};
+    use std::collections::HashSet;
+
+    const SIZES: [(usize, usize); 6] = [(1, 1), (7, 3), (3, 7), (80, 24), (33, 50), (120, 17)];
+
+    #[test]
+    fn when_generating_cells_should_cover_grid_once() {
+        for pattern in FillPattern::ALL {
+            for (w, h) in SIZES {
+                let cells = pattern.cells(w, h);
+                let unique: HashSet<_> = cells.iter().collect();
+                assert_eq!(w * h, cells.len(), "{pattern:?} {w}x{h}");
+                assert_eq!(w * h, unique.len(), "{pattern:?} {w}x{h}");
+                assert!(cells.iter().all(|&(x, y)| x < w && y < h));
+            }
+        }
+    }
+
+    #[test]
+    fn when_hilbert_should_move_one_cell_at_a_time() {
+        for (w, h) in SIZES {
+            let cells = FillPattern::Hilbert.cells(w, h);
+            for pair in cells.windows(2) {
+                let (a, b) = (pair[0], pair[1]);
+                assert_eq!(1, a.0.abs_diff(b.0) + a.1.abs_diff(b.1), "{w}x{h}");
+            }
+        }
+    }
+
+    #[test]
+    fn when_empty_grid_should_have_no_cells() {
+        for pattern in FillPattern::ALL {
+            assert!(pattern.cells(0, 10).is_empty());
+            assert!(pattern.cells(10, 0).is_empty());
+        }
+    }
+
+    fn painted_cells(pattern: FillPattern, area: Rect, perc: f64) -> usize {
+        let (width, height) = (f64::from(area.width), f64::from(area.height));
+        let fill = Fill::new(pattern, Color::Red)
+            .with_gbcolor(Color::Blue)
+            .center(width, height, perc);
+        let mut buf = Buffer::empty(area);
+        Canvas::default()
+            .marker(symbols::Marker::Block)
+            .x_bounds([0.0, width])
+            .y_bounds([0.0, height])
+            .paint(|ctx| ctx.draw(&fill))
+            .render(area, &mut buf);
+        buf.content().iter().filter(|c| c.fg == Color::Red).count()
+    }
+
+    #[test]
+    fn when_filling_canvas_should_paint_proportional_to_progress() {
+        let area = Rect::new(0, 0, 90, 31);
+        let total = f64::from(area.width) * f64::from(area.height);
+        for pattern in FillPattern::ALL {
+            for perc in [0.0, 0.01, 0.25, 0.5, 0.77, 0.999, 1.0] {
+                let painted = painted_cells(pattern, area, perc) as f64;
+                let expected = perc * total;
+                assert!(
+                    (painted - expected).abs() <= 1.0,
+                    "{pattern:?} at {perc}: painted {painted} expected {expected}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn when_complete_should_fill_non_square_canvas() {
+        let area = Rect::new(0, 0, 57, 12);
+        for pattern in FillPattern::ALL {
+            assert_eq!(57 * 12, painted_cells(pattern, area, 1.0), "{pattern:?}");
+        }
+    }
+}
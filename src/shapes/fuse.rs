@@ -0,0 +1,159 @@
+use std::{f64::consts::TAU, time::Duration};
+
+use ratatui::{
+    style::Color,
+    symbols,
+    widgets::canvas::{Painter, Shape},
+};
+
+use super::particles::{Emitter, ParticleSystem};
+use super::{filled_count, progress_color, Gradient, PathBuilder, Traced};
+use crate::theme;
+
+/// A fuse burning from left to right, a flame of sparks rides the burn point.
+///
+/// Particles live in normalized canvas coordinates (`0.0..=1.0` on both axes) so
+/// they survive a resize.
+#[derive(Debug, Clone)]
+pub struct Fuse {
+    pub width: f64,
+    pub height: f64,
+    pub burn_perc: f64,
+    pub color: Color,
+    pub bgcolor: Option<Color>,
+    pub gradient: Option<Gradient>,
+    sparks: ParticleSystem,
+    ramp: Gradient,
+}
+
+// vertical position of the fuse for a normalized x
+fn fuse_y(x: f64) -> f64 {
+    0.5 + 0.15 * (x * 2.0 * TAU).sin()
+}
+
+impl Fuse {
+    pub fn new(color: Color, seed: u64) -> Self {
+        let flame = Emitter::new((0.0, fuse_y(0.0)), 120.0)
+            .direction(TAU / 4.0, TAU / 3.0)
+            .speed(0.05..0.25)
+            .lifetime(0.2..0.8);
+        Self {
+            width: 0.0,
+            height: 0.0,
+            burn_perc: 0.0,
+            color,
+            bgcolor: None,
+            gradient: None,
+            sparks: ParticleSystem::new(seed)
+                .with_emitter(flame)
+                .with_gravity((0.0, -0.3))
+                .with_drag(1.5),
+            ramp: Gradient::evenly(&[
+                theme::WHITE,
+                theme::LIGHT_YELLOW,
+                theme::AMBER,
+                theme::RED,
+                theme::DARK_GRAY,
+            ]),
+        }
+    }
+
+    pub fn get_marker() -> symbols::Marker {
+        symbols::Marker::Braille
+    }
+
+    pub fn with_gbcolor(mut self, bg: Color) -> Self {
+        self.bgcolor = Some(bg);
+        self
+    }
+
+    pub fn with_gradient(mut self, gradient: Gradient) -> Self {
+        self.gradient = Some(gradient);
+        self
+    }
+
+    pub fn center(self, width: f64, height: f64, burn_perc: f64) -> Self {
+        Self {
+            width,
+            height,
+            burn_perc,
+            color: progress_color(&self.gradient, self.color, burn_perc),
+            ..self
+        }
+    }
+
+    /// Moves the flame to the burn point and advances the sparks.
+    pub fn tick(&mut self, elapsed: Duration, burn_perc: f64) {
+        let x = burn_perc.clamp(0.0, 1.0);
+        for e in &mut self.sparks.emitters {
+            e.pos = (x, fuse_y(x));
+            e.active = burn_perc < 1.0;
+        }
+        self.sparks.tick(elapsed);
+    }
+
+    pub fn sparks(&self) -> &ParticleSystem {
+        &self.sparks
+    }
+}
+
+impl Traced for Fuse {
+    fn trace(&self, painter: &mut Painter<'_, '_>) -> Vec<(usize, usize)> {
+        let mut path = PathBuilder::default();
+        let steps = (self.width * 4.0) as u32;
+        for i in 0..=steps {
+            let x = f64::from(i) / f64::from(steps.max(1));
+            path.push(painter.get_point(x * self.width, fuse_y(x) * self.height));
+        }
+        path.cells
+    }
+
+    fn draw_traced(&self, painter: &mut Painter<'_, '_>, cells: &[(usize, usize)]) {
+        // burnt part is ash, what is left of the fuse has the progress colour
+        let burnt = filled_count(cells.len(), self.burn_perc);
+        let (ash, fuse) = cells.split_at(burnt);
+        if let Some(c) = self.bgcolor {
+            for &(x, y) in ash {
+                painter.paint(x, y, c);
+            }
+        }
+        for &(x, y) in fuse {
+            painter.paint(x, y, self.color);
+        }
+        for p in self.sparks.particles() {
+            if let Some((x, y)) = painter.get_point(p.pos.0 * self.width, p.pos.1 * self.height) {
+                painter.paint(x, y, self.ramp.at(p.life()));
+            }
+        }
+    }
+}
+
+impl Shape for Fuse {
+    fn draw(&self, painter: &mut Painter<'_, '_>) {
+        let cells = self.trace(painter);
+        self.draw_traced(painter, &cells);
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn when_burning_should_emit_from_burn_point() {
+        let mut f = Fuse::new(Color::Red, 42);
+        f.tick(Duration::from_millis(50), 0.25);
+        let sparks = f.sparks().particles();
+        assert!(!sparks.is_empty());
+        assert!(sparks.iter().all(|p| (p.pos.0 - 0.25).abs() < 0.05));
+    }
+
+    #[test]
+    fn when_burnt_should_stop_sparks() {
+        let mut f = Fuse::new(Color::Red, 42);
+        f.tick(Duration::from_millis(100), 0.5);
+        f.tick(Duration::from_secs(1), 1.0);
+        assert!(f.sparks().particles().is_empty());
+    }
+}
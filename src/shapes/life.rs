@@ -0,0 +1,341 @@
+use std::time::Duration;
+
+use ratatui::{buffer::Buffer, layout::Rect, style::Color};
+
+use super::{progress_color, splitmix64, CellRenderer, Gradient};
+
+/// Conway's Game of Life board, one bit per cell, 64 cells per word.
+///
+/// Cells outside the board are dead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Board {
+    width: usize,
+    height: usize,
+    words: usize, // per row
+    bits: Vec<u64>,
+}
+
+impl Board {
+    pub fn new(width: usize, height: usize) -> Self {
+        let words = width.div_ceil(64);
+        Self {
+            width,
+            height,
+            words,
+            bits: vec![0; words * height],
+        }
+    }
+
+    /// Roughly a third of the cells alive, always the same for a given seed.
+    pub fn seeded(width: usize, height: usize, seed: u64) -> Self {
+        let mut board = Self::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let h = splitmix64(seed ^ ((y as u64) << 32) ^ x as u64);
+                board.set(x, y, h.is_multiple_of(3));
+            }
+        }
+        board
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        self.bits[y * self.words + x / 64] >> (x % 64) & 1 == 1
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, alive: bool) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let word = &mut self.bits[y * self.words + x / 64];
+        if alive {
+            *word |= 1 << (x % 64);
+        } else {
+            *word &= !(1 << (x % 64));
+        }
+    }
+
+    pub fn population(&self) -> usize {
+        self.bits.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    fn row(&self, y: usize) -> &[u64] {
+        &self.bits[y * self.words..(y + 1) * self.words]
+    }
+
+    /// Next generation, 64 cells at a time with a bit-sliced neighbour count.
+    pub fn step(&self) -> Self {
+        let mut next = Self::new(self.width, self.height);
+        let empty = vec![0; self.words];
+        let last_mask = match self.width % 64 {
+            0 => u64::MAX,
+            r => (1 << r) - 1,
+        };
+        for y in 0..self.height {
+            let above = if y > 0 { self.row(y - 1) } else { &empty };
+            let below = if y + 1 < self.height {
+                self.row(y + 1)
+            } else {
+                &empty
+            };
+            let current = self.row(y);
+            for i in 0..self.words {
+                // bit x of `left(row)` holds cell x - 1, of `right(row)` cell x + 1
+                let left = |r: &[u64]| r[i] << 1 | if i > 0 { r[i - 1] >> 63 } else { 0 };
+                let right = |r: &[u64]| {
+                    r[i] >> 1
+                        | if i + 1 < self.words {
+                            r[i + 1] << 63
+                        } else {
+                            0
+                        }
+                };
+                let neighbours = [
+                    left(above),
+                    above[i],
+                    right(above),
+                    left(current),
+                    right(current),
+                    left(below),
+                    below[i],
+                    right(below),
+                ];
+                // count modulo 8 in three bit planes, 8 neighbours wraps to 0 which dies too
+                let (mut s0, mut s1, mut s2) = (0u64, 0u64, 0u64);
+                for n in neighbours {
+                    let c0 = s0 & n;
+                    s0 ^= n;
+                    let c1 = s1 & c0;
+                    s1 ^= c0;
+                    s2 ^= c1;
+                }
+                let alive = current[i];
+                let mut word = !s2 & s1 & (s0 | alive);
+                if i + 1 == self.words {
+                    word &= last_mask;
+                }
+                next.bits[y * self.words + i] = word;
+            }
+        }
+        next
+    }
+
+    fn fingerprint(&self) -> u64 {
+        self.bits
+            .iter()
+            .fold(0xcbf2_9ce4_8422_2325, |h, w| splitmix64(h ^ *w))
+    }
+}
+
+/// FNV-1a of the label, so a timer always starts from the same board.
+pub fn label_seed(label: &str) -> u64 {
+    label.bytes().fold(0xcbf2_9ce4_8422_2325, |h, b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+const GENERATION: Duration = Duration::from_secs(1);
+
+/// Game of Life evolving once a second, the living cells left of the progress are
+/// drawn as expired. Two rows of cells per terminal row.
+///
+/// When the board dies out or settles, a new soup is dropped in so long timers
+/// keep moving.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Life {
+    pub fill_perc: f64,
+    pub color: Color,
+    pub bgcolor: Option<Color>,
+    pub gradient: Option<Gradient>,
+    seed: u64,
+    board: Board,
+    generation: u64,
+    since_step: Duration,
+    history: [u64; 2], // fingerprints of the last generations to spot still lifes/blinkers
+}
+
+impl Life {
+    pub fn new(color: Color, seed: u64) -> Self {
+        Self {
+            fill_perc: 0.0,
+            color,
+            bgcolor: None,
+            gradient: None,
+            seed,
+            board: Board::new(0, 0),
+            generation: 0,
+            since_step: Duration::ZERO,
+            history: [0; 2],
+        }
+    }
+
+    pub fn with_gbcolor(mut self, bg: Color) -> Self {
+        self.bgcolor = Some(bg);
+        self
+    }
+
+    pub fn with_gradient(mut self, gradient: Gradient) -> Self {
+        self.gradient = Some(gradient);
+        self
+    }
+
+    /// Restarts from the board of the given label.
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.seed = label_seed(label);
+        self.board = Board::seeded(self.board.width, self.board.height, self.seed);
+        self.generation = 0;
+        self
+    }
+
+    pub fn center(self, fill_perc: f64) -> Self {
+        Self {
+            fill_perc,
+            color: progress_color(&self.gradient, self.color, fill_perc),
+            ..self
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Resizes the board to `area` (borders excluded) and evolves it once a second.
+    pub fn tick(&mut self, elapsed: Duration, area: Rect) {
+        let (w, h) = (usize::from(area.width), usize::from(area.height) * 2);
+        if (w, h) != (self.board.width, self.board.height) {
+            self.board = Board::seeded(w, h, self.seed);
+            self.generation = 0;
+            self.since_step = Duration::ZERO;
+        }
+        self.since_step += elapsed;
+        while self.since_step >= GENERATION {
+            self.since_step -= GENERATION;
+            self.step();
+        }
+    }
+
+    fn step(&mut self) {
+        self.board = self.board.step();
+        self.generation += 1;
+        let print = self.board.fingerprint();
+        let settled = self.history.contains(&print);
+        self.history = [self.history[1], print];
+        let (w, h) = (self.board.width, self.board.height);
+        if settled || self.board.population() < w * h / 50 {
+            // drop a soup in a random quarter of the board
+            let soup = Board::seeded(w / 2, h / 2, splitmix64(self.seed ^ self.generation));
+            let (ox, oy) = (
+                splitmix64(self.generation) as usize % (w / 2 + 1),
+                splitmix64(!self.generation) as usize % (h / 2 + 1),
+            );
+            for y in 0..soup.height {
+                for x in 0..soup.width {
+                    if soup.get(x, y) {
+                        self.board.set(ox + x, oy + y, true);
+                    }
+                }
+            }
+        }
+    }
+
+    fn cell_color(&self, x: usize) -> Color {
+        let expired = (x as f64) < self.fill_perc * self.board.width as f64;
+        if expired {
+            self.bgcolor.unwrap_or(Color::DarkGray)
+        } else {
+            self.color
+        }
+    }
+}
+
+impl CellRenderer for Life {
+    fn render_cells(&self, area: Rect, buf: &mut Buffer) {
+        for row in 0..area.height {
+            for col in 0..area.width {
+                let (x, y) = (usize::from(col), usize::from(row) * 2);
+                let top = self.board.get(x, y);
+                let bottom = self.board.get(x, y + 1);
+                let Some(cell) = buf.cell_mut((area.x + col, area.y + row)) else {
+                    continue;
+                };
+                let c = self.cell_color(x);
+                match (top, bottom) {
+                    (true, true) => cell.set_char('█').set_fg(c),
+                    (true, false) => cell.set_char('▀').set_fg(c),
+                    (false, true) => cell.set_char('▄').set_fg(c),
+                    (false, false) => continue,
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn board_with(width: usize, height: usize, alive: &[(usize, usize)]) -> Board {
+        let mut b = Board::new(width, height);
+        for &(x, y) in alive {
+            b.set(x, y, true);
+        }
+        b
+    }
+
+    #[test]
+    fn when_blinker_should_oscillate() {
+        let b = board_with(5, 5, &[(1, 2), (2, 2), (3, 2)]);
+        let next = b.step();
+        assert_eq!(board_with(5, 5, &[(2, 1), (2, 2), (2, 3)]), next);
+        assert_eq!(b, next.step());
+    }
+
+    #[test]
+    fn when_blinker_across_words_should_oscillate() {
+        let b = board_with(130, 3, &[(63, 1), (64, 1), (65, 1)]);
+        let next = b.step();
+        assert_eq!(board_with(130, 3, &[(64, 0), (64, 1), (64, 2)]), next);
+        assert_eq!(b, next.step());
+    }
+
+    #[test]
+    fn when_glider_should_move_diagonally() {
+        let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+        let mut b = board_with(70, 10, &glider);
+        for _ in 0..4 {
+            b = b.step();
+        }
+        let moved: Vec<_> = glider.iter().map(|&(x, y)| (x + 1, y + 1)).collect();
+        assert_eq!(board_with(70, 10, &moved), b);
+    }
+
+    #[test]
+    fn when_cells_on_edge_should_not_wrap() {
+        let b = board_with(64, 3, &[(63, 0), (63, 1), (63, 2)]);
+        assert_eq!(board_with(64, 3, &[(62, 1), (63, 1)]), b.step());
+    }
+
+    #[test]
+    fn when_same_label_should_seed_same_board() {
+        let area = Rect::new(0, 0, 90, 30);
+        let mut a = Life::new(Color::Red, 0).with_label("tea");
+        let mut b = Life::new(Color::Red, 0).with_label("tea");
+        let mut c = Life::new(Color::Red, 0).with_label("coffee");
+        a.tick(Duration::from_secs(3), area);
+        b.tick(Duration::from_secs(3), area);
+        c.tick(Duration::from_secs(3), area);
+        assert_eq!(a.board(), b.board());
+        assert_ne!(a.board(), c.board());
+    }
+}
@@ -0,0 +1,224 @@
+use std::sync::Arc;
+
+use ratatui::{
+    style::Color,
+    symbols,
+    widgets::canvas::{Painter, Shape},
+};
+
+use super::{progress_color, splitmix64, Gradient, Traced, TracedLook};
+use crate::image::Image;
+
+/// Order in which the pixels of the image show up.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Reveal {
+    /// one pixel at a time, scattered over the image
+    #[default]
+    Pixel,
+    /// top to bottom, left to right
+    Scanline,
+    /// from the center outward
+    Spiral,
+}
+
+/// Canvas pixels per terminal cell for a marker.
+pub fn marker_resolution(marker: symbols::Marker) -> (usize, usize) {
+    match marker {
+        symbols::Marker::Braille => (2, 4),
+        symbols::Marker::HalfBlock => (1, 2),
+        _ => (1, 1),
+    }
+}
+
+/// An image revealed as the timer progresses, scaled to fit the canvas.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mask {
+    pub width: f64,
+    pub height: f64,
+    pub reveal: Reveal,
+    pub invert: bool,
+    pub marker: symbols::Marker,
+    pub fill_perc: f64,
+    pub color: Color,
+    pub bgcolor: Option<Color>,
+    pub gradient: Option<Gradient>,
+    image: Arc<Image>,
+}
+
+impl Mask {
+    pub fn new(image: Image, reveal: Reveal, color: Color) -> Self {
+        Self {
+            width: 0.0,
+            height: 0.0,
+            reveal,
+            invert: false,
+            marker: symbols::Marker::HalfBlock,
+            fill_perc: 0.0,
+            color,
+            bgcolor: None,
+            gradient: None,
+            image: Arc::new(image),
+        }
+    }
+
+    pub fn with_invert(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    pub fn with_marker(mut self, marker: symbols::Marker) -> Self {
+        self.marker = marker;
+        self
+    }
+
+    pub fn look(&self) -> TracedLook<'_> {
+        TracedLook {
+            perc: self.fill_perc,
+            color: self.color,
+            bgcolor: self.bgcolor,
+            along: None,
+        }
+    }
+
+    pub fn get_marker(&self) -> symbols::Marker {
+        self.marker
+    }
+
+    pub fn with_gbcolor(mut self, bg: Color) -> Self {
+        self.bgcolor = Some(bg);
+        self
+    }
+
+    pub fn with_gradient(mut self, gradient: Gradient) -> Self {
+        self.gradient = Some(gradient);
+        self
+    }
+
+    pub fn center(self, width: f64, height: f64, fill_perc: f64) -> Self {
+        Self {
+            width,
+            height,
+            fill_perc,
+            color: progress_color(&self.gradient, self.color, fill_perc),
+            ..self
+        }
+    }
+
+    /// Ink cells of the image on a `grid_w` x `grid_h` grid, in reveal order.
+    pub fn ink_cells(&self, grid_w: usize, grid_h: usize) -> Vec<(usize, usize)> {
+        let img = &self.image;
+        if img.width == 0 || img.height == 0 || grid_w == 0 || grid_h == 0 {
+            return vec![];
+        }
+        // cells are about twice as tall as wide, a grid pixel is 1/mx wide and 2/my tall
+        let (mx, my) = marker_resolution(self.marker);
+        let (px_w, px_h) = (1.0 / mx as f64, 2.0 / my as f64);
+        let (area_w, area_h) = (grid_w as f64 * px_w, grid_h as f64 * px_h);
+        let scale = (area_w / img.width as f64).min(area_h / img.height as f64);
+        let (off_x, off_y) = (
+            (area_w - img.width as f64 * scale) / 2.0,
+            (area_h - img.height as f64 * scale) / 2.0,
+        );
+
+        let mut cells = vec![];
+        for gy in 0..grid_h {
+            for gx in 0..grid_w {
+                let ix = ((gx as f64 + 0.5) * px_w - off_x) / scale;
+                let iy = ((gy as f64 + 0.5) * px_h - off_y) / scale;
+                if ix >= 0.0 && iy >= 0.0 && img.is_ink(ix as usize, iy as usize, self.invert) {
+                    cells.push((gx, gy));
+                }
+            }
+        }
+
+        match self.reveal {
+            Reveal::Scanline => {}
+            Reveal::Spiral => {
+                let (cx, cy) = (grid_w as f64 / 2.0, grid_h as f64 / 2.0);
+                let key = |&(x, y): &(usize, usize)| {
+                    // physical distance so the rings look round
+                    let dx = (x as f64 - cx) * px_w;
+                    let dy = (y as f64 - cy) * px_h;
+                    let ring = dx.hypot(dy).floor();
+                    (ring, dy.atan2(dx))
+                };
+                cells.sort_by(|a, b| {
+                    let (ka, kb) = (key(a), key(b));
+                    ka.0.total_cmp(&kb.0).then(ka.1.total_cmp(&kb.1))
+                });
+            }
+            Reveal::Pixel => {
+                // stable scatter: sort on a hash of the position
+                cells.sort_by_key(|&(x, y)| splitmix64((x as u64) << 32 | y as u64));
+            }
+        }
+        cells
+    }
+}
+
+impl Traced for Mask {
+    fn trace(&self, painter: &mut Painter<'_, '_>) -> Vec<(usize, usize)> {
+        // bottom right corner of the canvas gives the grid resolution
+        let Some((max_x, max_y)) = painter.get_point(self.width, 0.0) else {
+            return vec![];
+        };
+        self.ink_cells(max_x + 1, max_y + 1)
+    }
+
+    fn draw_traced(&self, painter: &mut Painter<'_, '_>, cells: &[(usize, usize)]) {
+        self.look().paint(painter, cells);
+    }
+}
+
+impl Shape for Mask {
+    fn draw(&self, painter: &mut Painter<'_, '_>) {
+        let cells = self.trace(painter);
+        self.draw_traced(painter, &cells);
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    // 4x4 image, the left half is ink
+    fn half() -> Image {
+        Image::decode(b"P1 4 4 1100 1100 1100 1100").unwrap()
+    }
+
+    #[test]
+    fn when_revealing_should_keep_same_cells_in_any_order() {
+        let mut orders: Vec<Vec<(usize, usize)>> =
+            [Reveal::Pixel, Reveal::Scanline, Reveal::Spiral]
+                .into_iter()
+                .map(|r| Mask::new(half(), r, Color::Red).ink_cells(40, 40))
+                .collect();
+        for o in &mut orders {
+            o.sort();
+        }
+        assert!(!orders[0].is_empty());
+        assert_eq!(orders[0], orders[1]);
+        assert_eq!(orders[1], orders[2]);
+    }
+
+    #[test]
+    fn when_scaled_should_keep_aspect_ratio() {
+        // half block pixels are square: a square image on a wide grid stays square
+        let cells = Mask::new(half(), Reveal::Scanline, Color::Red).ink_cells(100, 40);
+        let xs = cells.iter().map(|c| c.0);
+        let (min, max) = (xs.clone().min().unwrap(), xs.max().unwrap());
+        assert_eq!(20, max - min + 1);
+        assert_eq!(800, cells.len());
+    }
+
+    #[test]
+    fn when_inverted_should_reveal_other_half() {
+        let mask = Mask::new(half(), Reveal::Scanline, Color::Red);
+        let ink = mask.ink_cells(40, 40);
+        let inverted = mask.with_invert(true).ink_cells(40, 40);
+        assert_eq!(800, ink.len());
+        assert_eq!(800, inverted.len());
+        assert!(ink.iter().all(|c| !inverted.contains(c)));
+    }
+}
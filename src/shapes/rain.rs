@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use ratatui::{buffer::Buffer, layout::Rect, style::Color};
+
+use super::{progress_color, splitmix64, CellRenderer, Gradient};
+use crate::theme;
+
+const GLYPHS: &[char] = &[
+    'ｱ', 'ｲ', 'ｳ', 'ｴ', 'ｵ', 'ｶ', 'ｷ', 'ｸ', 'ｹ', 'ｺ', 'ｻ', 'ｼ', 'ｽ', 'ｾ', 'ｿ', 'ﾀ', 'ﾁ', 'ﾂ', 'ﾃ',
+    'ﾄ', 'ﾅ', 'ﾆ', 'ﾇ', 'ﾈ', 'ﾉ', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ':', '=', '+',
+    '<', '>',
+];
+
+/// Falling glyph columns, the share of raining columns follows the progress.
+///
+/// Drops are a pure function of the elapsed time so nothing is lost on resize.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rain {
+    pub fill_perc: f64,
+    pub color: Color,
+    pub bgcolor: Option<Color>,
+    pub gradient: Option<Gradient>,
+    pub trail: u16,
+    seed: u64,
+    time: f64,
+}
+
+fn unit(x: u64) -> f64 {
+    (x % 10_000) as f64 / 10_000.0
+}
+
+impl Rain {
+    pub fn new(color: Color, seed: u64) -> Self {
+        Self {
+            fill_perc: 0.0,
+            color,
+            bgcolor: None,
+            gradient: None,
+            trail: 12,
+            seed,
+            time: 0.0,
+        }
+    }
+
+    pub fn with_gbcolor(mut self, bg: Color) -> Self {
+        self.bgcolor = Some(bg);
+        self
+    }
+
+    pub fn with_gradient(mut self, gradient: Gradient) -> Self {
+        self.gradient = Some(gradient);
+        self
+    }
+
+    pub fn center(self, fill_perc: f64) -> Self {
+        Self {
+            fill_perc,
+            color: progress_color(&self.gradient, self.color, fill_perc),
+            ..self
+        }
+    }
+
+    pub fn tick(&mut self, elapsed: Duration) {
+        self.time += elapsed.as_secs_f64();
+    }
+
+    /// Whether column `x` is raining at the current progress.
+    pub fn is_raining(&self, x: u16) -> bool {
+        unit(splitmix64(self.seed ^ u64::from(x))) < self.fill_perc
+    }
+
+    // row of the drop head in column `x`, can be above or below the area
+    fn head(&self, x: u16, height: u16) -> i64 {
+        let h = splitmix64(self.seed.wrapping_add(u64::from(x) << 32));
+        let speed = 6.0 + 14.0 * unit(h);
+        let cycle = f64::from(height + self.trail);
+        let offset = cycle * unit(h >> 16);
+        ((offset + self.time * speed) % cycle) as i64
+    }
+
+    fn glyph(&self, x: u16, y: u16) -> char {
+        // glyphs change a few times a second
+        let step = (self.time * 8.0) as u64;
+        let h = splitmix64(self.seed ^ (u64::from(x) << 40) ^ (u64::from(y) << 20) ^ step);
+        GLYPHS[(h % GLYPHS.len() as u64) as usize]
+    }
+}
+
+impl CellRenderer for Rain {
+    fn render_cells(&self, area: Rect, buf: &mut Buffer) {
+        let bg = self.bgcolor.unwrap_or(Color::Reset);
+        let fade = Gradient::evenly(&[self.color, bg]);
+        for x in 0..area.width {
+            if !self.is_raining(x) {
+                continue;
+            }
+            let head = self.head(x, area.height);
+            for y in 0..area.height {
+                let dist = head - i64::from(y);
+                if dist < 0 || dist >= i64::from(self.trail) {
+                    continue;
+                }
+                let fg = if dist == 0 {
+                    theme::WHITE
+                } else {
+                    fade.at(dist as f64 / f64::from(self.trail))
+                };
+                if let Some(cell) = buf.cell_mut((area.x + x, area.y + y)) {
+                    cell.set_char(self.glyph(x, y)).set_fg(fg);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn when_progress_grows_should_rain_on_more_columns() {
+        let rain = Rain::new(Color::Green, 9);
+        let raining = |perc: f64| {
+            let r = rain.clone().center(perc);
+            (0..1000).filter(|x| r.is_raining(*x)).count()
+        };
+        assert_eq!(0, raining(0.0));
+        assert_eq!(1000, raining(1.0));
+        assert!((raining(0.5) as i64 - 500).abs() < 60);
+        assert!(raining(0.3) < raining(0.6));
+    }
+
+    #[test]
+    fn when_rendering_should_only_draw_raining_columns() {
+        let area = Rect::new(0, 0, 60, 20);
+        let mut rain = Rain::new(Color::Green, 3).center(0.4);
+        rain.tick(Duration::from_millis(700));
+        let mut buf = Buffer::empty(area);
+        rain.render_cells(area, &mut buf);
+        for x in 0..area.width {
+            let drawn = (0..area.height).any(|y| buf[(x, y)].symbol() != " ");
+            if drawn {
+                assert!(rain.is_raining(x));
+            }
+        }
+    }
+}
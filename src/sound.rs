@@ -0,0 +1,110 @@
+use std::{fmt, str::FromStr};
+
+use color_eyre::{eyre::eyre, Result};
+use rand::Rng;
+use rust_embed::RustEmbed;
+
+#[derive(RustEmbed)]
+#[folder = "assets/"]
+struct Asset;
+
+/// Names of the embedded sounds, the file names without extension.
+pub fn names() -> Vec<String> {
+    Asset::iter()
+        .filter_map(|f| f.rsplit_once('.').map(|(stem, _)| stem.to_string()))
+        .collect()
+}
+
+/// Sound played when the countdown completes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum Sound {
+    #[default]
+    Random,
+    Named(String),
+    Off,
+}
+
+impl FromStr for Sound {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "random" => Ok(Sound::Random),
+            "none" | "off" => Ok(Sound::Off),
+            _ if names().iter().any(|n| n == s) => Ok(Sound::Named(s.to_string())),
+            _ => Err(eyre!(
+                "unknown sound `{s}`, expected random, none or one of {}",
+                names().join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Sound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sound::Random => f.write_str("random"),
+            Sound::Named(n) => f.write_str(n),
+            Sound::Off => f.write_str("none"),
+        }
+    }
+}
+
+impl Sound {
+    fn file(&self) -> Option<String> {
+        let files: Vec<_> = Asset::iter().collect();
+        match self {
+            Sound::Off => None,
+            Sound::Random if files.is_empty() => None,
+            Sound::Random => {
+                let i = rand::thread_rng().gen_range(0..files.len());
+                Some(files[i].to_string())
+            }
+            Sound::Named(n) => files
+                .into_iter()
+                .find(|f| f.rsplit_once('.').is_some_and(|(stem, _)| stem == n))
+                .map(|f| f.to_string()),
+        }
+    }
+
+    /// Plays the sound in the background, an unavailable audio device is ignored.
+    pub fn play(&self) {
+        self.play_then(|_| {});
+    }
+
+    /// Plays the sound in the background, `done` gets the outcome once it ended.
+    pub fn play_then(&self, done: impl FnOnce(Result<(), String>) + Send + 'static) {
+        let Some(file) = self.file() else {
+            return;
+        };
+        std::thread::spawn(move || done(play_file(&file)));
+    }
+}
+
+// blocks until the end of `file`
+fn play_file(file: &str) -> Result<(), String> {
+    let (_stream, handle) = rodio::OutputStream::try_default().map_err(|e| e.to_string())?;
+    let data = Asset::get(file).ok_or_else(|| format!("no sound {file}"))?;
+    let reader = std::io::BufReader::new(std::io::Cursor::new(data.data));
+    let source = rodio::Decoder::new(reader).map_err(|e| format!("{file}: {e}"))?;
+    let sink = rodio::Sink::try_new(&handle).map_err(|e| e.to_string())?;
+    sink.append(source);
+    sink.sleep_until_end();
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn when_parsing_should_only_accept_embedded_sounds() {
+        assert_eq!(Sound::Random, "random".parse().unwrap());
+        assert_eq!(Sound::Off, "none".parse().unwrap());
+        let pants: Sound = "PantsTime".parse().unwrap();
+        assert_eq!(Some("PantsTime.ogg".to_string()), pants.file());
+        assert_eq!("PantsTime", pants.to_string());
+        assert!("Pants".parse::<Sound>().is_err());
+    }
+}
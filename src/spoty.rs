@@ -1,25 +1,55 @@
 use rspotify::{
-    model::{device, playlist::*, Market, PrivateUser},
+    model::{playlist::*, Market, PrivateUser},
     prelude::*,
     scopes, AuthCodePkceSpotify, ClientResult, Config, Credentials, OAuth,
 };
 
-#[derive(Debug)]
+use std::path::PathBuf;
+
+use color_eyre::{eyre::eyre, Result};
+use serde::{Deserialize, Serialize};
+
+/// Spotify app settings, unset values are read by rspotify from the
+/// `RSPOTIFY_*` variables or the `.env` file.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Settings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect_uri: Option<String>,
+    /// where the token is kept between runs [default: ./token_cache.json]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_cache: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
 pub struct SpotiApi {
     pub api: AuthCodePkceSpotify,
 }
 
 impl SpotiApi {
-    /// Creates a new [`SpotiApi`].
-    pub fn new() -> Self {
+    /// Creates a new [`SpotiApi`], the client id comes from the environment.
+    pub fn new() -> Result<Self> {
+        Self::with_settings(&Settings::default())
+    }
+
+    /// Fails without a client id in `settings` nor the environment.
+    pub fn with_settings(settings: &Settings) -> Result<Self> {
         let config = Config {
             token_cached: true,
-            cache_path: "./token_cache.json".into(),
+            cache_path: settings
+                .token_cache
+                .clone()
+                .unwrap_or_else(|| PathBuf::from("./token_cache.json")),
             ..Default::default()
         };
-        //TODO: if this fail we have to somehow say that we need the creds
-        //maybe inject the creds instead!
-        let creds = Credentials::from_env().unwrap();
+        let creds = match &settings.client_id {
+            Some(id) => Credentials::new_pkce(id),
+            None => Credentials::from_env().ok_or_else(|| {
+                eyre!("no client id, set `client-id` in [spotify] or RSPOTIFY_CLIENT_ID")
+            })?,
+        };
 
         let scopes = scopes!(
             "playlist-read-collaborative",
@@ -37,18 +67,20 @@ impl SpotiApi {
             "user-read-private",
             "user-read-recently-played"
         );
-        let oauth = OAuth::from_env(scopes).unwrap();
+        let mut oauth = OAuth::from_env(scopes.clone()).unwrap_or_else(|| OAuth {
+            scopes,
+            ..Default::default()
+        });
+        if let Some(uri) = &settings.redirect_uri {
+            oauth.redirect_uri.clone_from(uri);
+        }
         let spotify = AuthCodePkceSpotify::with_config(creds.clone(), oauth.clone(), config);
 
-        Self { api: spotify }
+        Ok(Self { api: spotify })
     }
 
     pub async fn get_user_info(&self) -> Option<PrivateUser> {
-        let user = self.api.me().await;
-        match user {
-            Ok(u) => Some(u),
-            Err(_) => None,
-        }
+        self.api.me().await.ok()
     }
     pub async fn get_user_playlist(&self) -> ClientResult<Vec<SimplifiedPlaylist>> {
         let limit = 50;
@@ -164,9 +196,3 @@ impl SpotiApi {
     //     Ok(code)
     // }
 }
-
-impl Default for SpotiApi {
-    fn default() -> Self {
-        Self::new()
-    }
-}
@@ -1,132 +1,804 @@
-use ratatui::style::{Color, Modifier, Style};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant, SystemTime},
+};
+
+use color_eyre::{eyre::eyre, Result};
+use ratatui::{
+    buffer::Buffer,
+    style::{
+        palette::tailwind::{BLUE, SLATE},
+        Color, Modifier, Style,
+    },
+};
+use serde::{Deserialize, Serialize};
 
+/// Styles of every surface of the UI.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Theme {
+    /// background of the whole app
     pub root: Style,
-    pub content: Style,
-    pub app_title: Style,
-    pub tabs: Style,
-    pub tabs_selected: Style,
-    pub borders: Style,
-    pub description: Style,
-    pub description_title: Style,
+    pub title_bar: TitleBar,
     pub key_binding: KeyBinding,
-    // pub logo: Logo,
-    // pub email: Email,
-    // pub traceroute: Traceroute,
-    // pub recipe: Recipe,
+    pub canvas: CanvasColors,
+    pub player: Player,
+    pub popup: Popup,
+}
+
+/// Time left and total duration at the top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TitleBar {
+    pub bar: Style,
+    pub key: Style,
+    pub value: Style,
+    pub progress: Style,
 }
 
+/// Key hints at the bottom.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct KeyBinding {
+    pub bar: Style,
     pub key: Style,
     pub description: Style,
 }
 
-// pub struct Logo {
-//     pub rat_eye: Color,
-//     pub rat_eye_alt: Color,
-// }
-
-// pub struct Email {
-//     pub tabs: Style,
-//     pub tabs_selected: Style,
-//     pub inbox: Style,
-//     pub item: Style,
-//     pub selected_item: Style,
-//     pub header: Style,
-//     pub header_value: Style,
-//     pub body: Style,
-// }
-
-// pub struct Traceroute {
-//     pub header: Style,
-//     pub selected: Style,
-//     pub ping: Style,
-//     pub map: Map,
-// }
-
-// pub struct Map {
-//     pub style: Style,
-//     pub color: Color,
-//     pub path: Color,
-//     pub source: Color,
-//     pub destination: Color,
-//     pub background_color: Color,
-// }
-
-// pub struct Recipe {
-//     pub ingredients: Style,
-//     pub ingredients_header: Style,
-// }
-
-pub const THEME: Theme = Theme {
+/// Colours of the animated shape, `bg` is used for the elapsed part.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanvasColors {
+    pub fg: Color,
+    pub bg: Color,
+}
+
+/// Music player action list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Player {
+    pub header: Style,
+    pub row: Color,
+    pub alt_row: Color,
+    pub selected: Style,
+    pub text: Color,
+}
+
+/// Help, logs, command palette and duration prompt, drawn over the rest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Popup {
+    pub body: Style,
+    pub selected: Style,
+    /// feedback of an input that is valid
+    pub ok: Style,
+    pub error: Style,
+}
+
+pub const DARK: Theme = Theme {
     root: Style::new().bg(DARK_BLUE),
-    content: Style::new().bg(DARK_BLUE).fg(LIGHT_GRAY),
-    app_title: Style::new()
-        .fg(WHITE)
-        .bg(DARK_BLUE)
-        .add_modifier(Modifier::BOLD),
-    tabs: Style::new().fg(MID_GRAY).bg(DARK_BLUE),
-    tabs_selected: Style::new()
-        .fg(WHITE)
-        .bg(DARK_BLUE)
-        .add_modifier(Modifier::BOLD)
-        .add_modifier(Modifier::REVERSED),
-    borders: Style::new().fg(LIGHT_GRAY),
-    description: Style::new().fg(LIGHT_GRAY).bg(DARK_BLUE),
-    description_title: Style::new().fg(LIGHT_GRAY).add_modifier(Modifier::BOLD),
-    // logo: Logo {
-    //     rat_eye: BLACK,
-    //     rat_eye_alt: RED,
-    // },
+    title_bar: TitleBar {
+        bar: Style::new().fg(Color::Indexed(236)).bg(Color::Indexed(232)),
+        key: Style::new().fg(BLACK).bg(DARK_GRAY),
+        value: Style::new().fg(DARK_GRAY).bg(BLACK),
+        progress: Style::new().fg(LIGHT_YELLOW).bg(DARK_GRAY),
+    },
     key_binding: KeyBinding {
+        bar: Style::new().fg(Color::Indexed(236)).bg(Color::Indexed(232)),
         key: Style::new().fg(BLACK).bg(DARK_GRAY),
         description: Style::new().fg(DARK_GRAY).bg(BLACK),
     },
-    // email: Email {
-    //     tabs: Style::new().fg(MID_GRAY).bg(DARK_BLUE),
-    //     tabs_selected: Style::new()
-    //         .fg(WHITE)
-    //         .bg(DARK_BLUE)
-    //         .add_modifier(Modifier::BOLD),
-    //     inbox: Style::new().bg(DARK_BLUE).fg(LIGHT_GRAY),
-    //     item: Style::new().fg(LIGHT_GRAY),
-    //     selected_item: Style::new().fg(LIGHT_YELLOW),
-    //     header: Style::new().add_modifier(Modifier::BOLD),
-    //     header_value: Style::new().fg(LIGHT_GRAY),
-    //     body: Style::new().bg(DARK_BLUE).fg(LIGHT_GRAY),
-    // },
-    // traceroute: Traceroute {
-    //     header: Style::new()
-    //         .bg(DARK_BLUE)
-    //         .add_modifier(Modifier::BOLD)
-    //         .add_modifier(Modifier::UNDERLINED),
-    //     selected: Style::new().fg(LIGHT_YELLOW),
-    //     ping: Style::new().fg(WHITE),
-    //     map: Map {
-    //         style: Style::new().bg(DARK_BLUE),
-    //         background_color: DARK_BLUE,
-    //         color: LIGHT_GRAY,
-    //         path: LIGHT_BLUE,
-    //         source: LIGHT_GREEN,
-    //         destination: LIGHT_RED,
-    //     },
-    // },
-    // recipe: Recipe {
-    //     ingredients: Style::new().bg(DARK_BLUE).fg(LIGHT_GRAY),
-    //     ingredients_header: Style::new()
-    //         .add_modifier(Modifier::BOLD)
-    //         .add_modifier(Modifier::UNDERLINED),
-    // },
+    canvas: CanvasColors {
+        fg: Color::LightRed,
+        bg: Color::DarkGray,
+    },
+    player: Player {
+        header: Style::new().fg(SLATE.c100).bg(BLUE.c800),
+        row: SLATE.c950,
+        alt_row: SLATE.c900,
+        selected: Style::new().bg(SLATE.c800).add_modifier(Modifier::BOLD),
+        text: SLATE.c200,
+    },
+    popup: Popup {
+        body: Style::new().fg(WHITE).bg(BLACK),
+        selected: Style::new().fg(BLACK).bg(LIGHT_BLUE),
+        ok: Style::new().fg(LIGHT_GREEN),
+        error: Style::new().fg(LIGHT_RED),
+    },
+};
+
+pub const LIGHT: Theme = Theme {
+    root: Style::new().bg(Color::Rgb(244, 244, 240)),
+    title_bar: TitleBar {
+        bar: Style::new().fg(MID_GRAY).bg(LIGHT_GRAY),
+        key: Style::new().fg(WHITE).bg(MID_GRAY),
+        value: Style::new().fg(BLACK).bg(LIGHT_GRAY),
+        progress: Style::new().fg(Color::Rgb(128, 64, 0)).bg(LIGHT_GRAY),
+    },
+    key_binding: KeyBinding {
+        bar: Style::new().fg(MID_GRAY).bg(LIGHT_GRAY),
+        key: Style::new().fg(WHITE).bg(MID_GRAY),
+        description: Style::new().fg(BLACK).bg(LIGHT_GRAY),
+    },
+    canvas: CanvasColors {
+        fg: Color::Rgb(200, 40, 40),
+        bg: LIGHT_GRAY,
+    },
+    player: Player {
+        header: Style::new().fg(SLATE.c900).bg(BLUE.c200),
+        row: SLATE.c50,
+        alt_row: SLATE.c100,
+        selected: Style::new().bg(SLATE.c300).add_modifier(Modifier::BOLD),
+        text: SLATE.c800,
+    },
+    popup: Popup {
+        body: Style::new().fg(BLACK).bg(WHITE),
+        selected: Style::new().fg(WHITE).bg(BLUE.c600),
+        ok: Style::new().fg(Color::Rgb(0, 128, 0)),
+        error: Style::new().fg(Color::Rgb(192, 32, 32)),
+    },
+};
+
+// https://ethanschoonover.com/solarized/
+const BASE03: Color = Color::Rgb(0, 43, 54);
+const BASE02: Color = Color::Rgb(7, 54, 66);
+const BASE01: Color = Color::Rgb(88, 110, 117);
+const BASE1: Color = Color::Rgb(147, 161, 161);
+const SOLAR_YELLOW: Color = Color::Rgb(181, 137, 0);
+const SOLAR_ORANGE: Color = Color::Rgb(203, 75, 22);
+const SOLAR_BLUE: Color = Color::Rgb(38, 139, 210);
+const SOLAR_GREEN: Color = Color::Rgb(133, 153, 0);
+const SOLAR_RED: Color = Color::Rgb(220, 50, 47);
+
+pub const SOLARIZED: Theme = Theme {
+    root: Style::new().bg(BASE03),
+    title_bar: TitleBar {
+        bar: Style::new().fg(BASE01).bg(BASE02),
+        key: Style::new().fg(BASE03).bg(BASE01),
+        value: Style::new().fg(BASE1).bg(BASE02),
+        progress: Style::new().fg(SOLAR_YELLOW).bg(BASE02),
+    },
+    key_binding: KeyBinding {
+        bar: Style::new().fg(BASE01).bg(BASE02),
+        key: Style::new().fg(BASE03).bg(BASE01),
+        description: Style::new().fg(BASE1).bg(BASE02),
+    },
+    canvas: CanvasColors {
+        fg: SOLAR_ORANGE,
+        bg: BASE02,
+    },
+    player: Player {
+        header: Style::new().fg(BASE03).bg(SOLAR_BLUE),
+        row: BASE03,
+        alt_row: BASE02,
+        selected: Style::new().fg(SOLAR_YELLOW).add_modifier(Modifier::BOLD),
+        text: BASE1,
+    },
+    popup: Popup {
+        body: Style::new().fg(BASE1).bg(BASE02),
+        selected: Style::new().fg(BASE03).bg(SOLAR_BLUE),
+        ok: Style::new().fg(SOLAR_GREEN),
+        error: Style::new().fg(SOLAR_RED),
+    },
+};
+
+pub const HIGH_CONTRAST: Theme = Theme {
+    root: Style::new().bg(Color::Black),
+    title_bar: TitleBar {
+        bar: Style::new().fg(Color::White).bg(Color::Black),
+        key: Style::new()
+            .fg(Color::Black)
+            .bg(Color::White)
+            .add_modifier(Modifier::BOLD),
+        value: Style::new().fg(Color::White).bg(Color::Black),
+        progress: Style::new()
+            .fg(Color::Yellow)
+            .bg(Color::Black)
+            .add_modifier(Modifier::BOLD),
+    },
+    key_binding: KeyBinding {
+        bar: Style::new().fg(Color::White).bg(Color::Black),
+        key: Style::new()
+            .fg(Color::Black)
+            .bg(Color::White)
+            .add_modifier(Modifier::BOLD),
+        description: Style::new().fg(Color::White).bg(Color::Black),
+    },
+    canvas: CanvasColors {
+        fg: Color::Yellow,
+        bg: Color::Blue,
+    },
+    player: Player {
+        header: Style::new()
+            .fg(Color::Black)
+            .bg(Color::White)
+            .add_modifier(Modifier::BOLD),
+        row: Color::Black,
+        alt_row: Color::Black,
+        selected: Style::new()
+            .fg(Color::Black)
+            .bg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+        text: Color::White,
+    },
+    popup: Popup {
+        body: Style::new().fg(Color::White).bg(Color::Black),
+        selected: Style::new()
+            .fg(Color::Black)
+            .bg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+        ok: Style::new().fg(Color::LightGreen),
+        error: Style::new().fg(Color::LightRed),
+    },
 };
 
+impl Default for Theme {
+    fn default() -> Self {
+        DARK
+    }
+}
+
+impl Theme {
+    pub const BUILTIN: [(&'static str, Theme); 4] = [
+        ("dark", DARK),
+        ("light", LIGHT),
+        ("solarized", SOLARIZED),
+        ("high-contrast", HIGH_CONTRAST),
+    ];
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        Self::BUILTIN
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, t)| t.clone())
+    }
+
+    /// Parses a theme file, every key is optional and overrides `base`:
+    ///
+    /// ```toml
+    /// base = "solarized" # a built-in theme, `dark` when missing
+    /// root = { bg = "#101830" }
+    ///
+    /// [title_bar]
+    /// progress = { fg = "light-yellow", bold = true }
+    ///
+    /// [canvas]
+    /// fg = "236" # 256 colour palette index
+    /// ```
+    pub fn parse(src: &str) -> Result<Self> {
+        let file: ThemeFile = toml::from_str(src)?;
+        file.apply()
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let src = std::fs::read_to_string(path).map_err(|e| eyre!("{}: {e}", path.display()))?;
+        Self::parse(&src).map_err(|e| eyre!("{}: {e}", path.display()))
+    }
+}
+
+fn parse_color(s: &str) -> Result<Color> {
+    Color::from_str(s).map_err(|_| eyre!("invalid colour `{s}`"))
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StyleDef {
+    fg: Option<String>,
+    bg: Option<String>,
+    bold: Option<bool>,
+    dim: Option<bool>,
+    italic: Option<bool>,
+    underlined: Option<bool>,
+    reversed: Option<bool>,
+}
+
+impl StyleDef {
+    fn apply(&self, mut style: Style) -> Result<Style> {
+        if let Some(fg) = &self.fg {
+            style = style.fg(parse_color(fg)?);
+        }
+        if let Some(bg) = &self.bg {
+            style = style.bg(parse_color(bg)?);
+        }
+        let modifiers = [
+            (self.bold, Modifier::BOLD),
+            (self.dim, Modifier::DIM),
+            (self.italic, Modifier::ITALIC),
+            (self.underlined, Modifier::UNDERLINED),
+            (self.reversed, Modifier::REVERSED),
+        ];
+        for (on, modifier) in modifiers {
+            match on {
+                Some(true) => style = style.add_modifier(modifier),
+                Some(false) => style = style.remove_modifier(modifier),
+                None => {}
+            }
+        }
+        Ok(style)
+    }
+}
+
+// overrides a style or a colour when the key is in the file
+fn set_style(target: &mut Style, def: &Option<StyleDef>) -> Result<()> {
+    if let Some(def) = def {
+        *target = def.apply(*target)?;
+    }
+    Ok(())
+}
+
+fn set_color(target: &mut Color, def: &Option<String>) -> Result<()> {
+    if let Some(c) = def {
+        *target = parse_color(c)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    base: Option<String>,
+    root: Option<StyleDef>,
+    #[serde(default)]
+    title_bar: TitleBarDef,
+    #[serde(default)]
+    key_binding: KeyBindingDef,
+    #[serde(default)]
+    canvas: CanvasDef,
+    #[serde(default)]
+    player: PlayerDef,
+    #[serde(default)]
+    popup: PopupDef,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TitleBarDef {
+    bar: Option<StyleDef>,
+    key: Option<StyleDef>,
+    value: Option<StyleDef>,
+    progress: Option<StyleDef>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyBindingDef {
+    bar: Option<StyleDef>,
+    key: Option<StyleDef>,
+    description: Option<StyleDef>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CanvasDef {
+    fg: Option<String>,
+    bg: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlayerDef {
+    header: Option<StyleDef>,
+    row: Option<String>,
+    alt_row: Option<String>,
+    selected: Option<StyleDef>,
+    text: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PopupDef {
+    body: Option<StyleDef>,
+    selected: Option<StyleDef>,
+    ok: Option<StyleDef>,
+    error: Option<StyleDef>,
+}
+
+impl ThemeFile {
+    fn apply(&self) -> Result<Theme> {
+        let mut t = match &self.base {
+            Some(name) => {
+                Theme::builtin(name).ok_or_else(|| eyre!("unknown base theme `{name}`"))?
+            }
+            None => DARK,
+        };
+        set_style(&mut t.root, &self.root)?;
+        set_style(&mut t.title_bar.bar, &self.title_bar.bar)?;
+        set_style(&mut t.title_bar.key, &self.title_bar.key)?;
+        set_style(&mut t.title_bar.value, &self.title_bar.value)?;
+        set_style(&mut t.title_bar.progress, &self.title_bar.progress)?;
+        set_style(&mut t.key_binding.bar, &self.key_binding.bar)?;
+        set_style(&mut t.key_binding.key, &self.key_binding.key)?;
+        set_style(
+            &mut t.key_binding.description,
+            &self.key_binding.description,
+        )?;
+        set_color(&mut t.canvas.fg, &self.canvas.fg)?;
+        set_color(&mut t.canvas.bg, &self.canvas.bg)?;
+        set_style(&mut t.player.header, &self.player.header)?;
+        set_color(&mut t.player.row, &self.player.row)?;
+        set_color(&mut t.player.alt_row, &self.player.alt_row)?;
+        set_style(&mut t.player.selected, &self.player.selected)?;
+        set_color(&mut t.player.text, &self.player.text)?;
+        set_style(&mut t.popup.body, &self.popup.body)?;
+        set_style(&mut t.popup.selected, &self.popup.selected)?;
+        set_style(&mut t.popup.ok, &self.popup.ok)?;
+        set_style(&mut t.popup.error, &self.popup.error)?;
+        Ok(t)
+    }
+}
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Reloads a theme file when its modification time changes.
+#[derive(Debug)]
+pub struct ThemeWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    checked: Instant,
+}
+
+impl ThemeWatcher {
+    /// Watches `path`, its current content is considered already loaded.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let modified = modified(&path);
+        Self {
+            path,
+            modified,
+            checked: Instant::now(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The reloaded theme when the file changed, checks at most twice a second.
+    pub fn poll(&mut self) -> Option<Result<Theme>> {
+        if self.checked.elapsed() < POLL_INTERVAL {
+            return None;
+        }
+        self.checked = Instant::now();
+        self.reload_if_changed()
+    }
+
+    fn reload_if_changed(&mut self) -> Option<Result<Theme>> {
+        let modified = modified(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        Some(Theme::load(&self.path))
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
 pub const DARK_BLUE: Color = Color::Rgb(16, 24, 48);
 pub const LIGHT_BLUE: Color = Color::Rgb(64, 96, 192);
 pub const LIGHT_YELLOW: Color = Color::Rgb(192, 192, 96);
 pub const LIGHT_GREEN: Color = Color::Rgb(64, 192, 96);
 pub const LIGHT_RED: Color = Color::Rgb(192, 96, 96);
+pub const AMBER: Color = Color::Rgb(255, 176, 0);
 pub const RED: Color = Color::Rgb(215, 0, 0);
 pub const BLACK: Color = Color::Rgb(8, 8, 8); // not really black, often #080808
 pub const DARK_GRAY: Color = Color::Rgb(68, 68, 68);
 pub const MID_GRAY: Color = Color::Rgb(128, 128, 128);
 pub const LIGHT_GRAY: Color = Color::Rgb(188, 188, 188);
 pub const WHITE: Color = Color::Rgb(238, 238, 238); // not really white, often #eeeeee
+
+/// `--color` switch.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorChoice {
+    /// detect from `NO_COLOR`, `COLORTERM` and `TERM`
+    #[default]
+    Auto,
+    /// truecolor
+    Always,
+    /// no colour at all, dark colours are dimmed instead
+    Never,
+    /// 256 colour palette
+    #[value(name = "256")]
+    #[serde(rename = "256")]
+    Ansi256,
+    /// 16 colour palette
+    #[value(name = "16")]
+    #[serde(rename = "16")]
+    Ansi16,
+}
+
+impl ColorChoice {
+    pub fn depth(self) -> ColorDepth {
+        match self {
+            ColorChoice::Auto => ColorDepth::from_env(),
+            ColorChoice::Always => ColorDepth::TrueColor,
+            ColorChoice::Never => ColorDepth::NoColor,
+            ColorChoice::Ansi256 => ColorDepth::Ansi256,
+            ColorChoice::Ansi16 => ColorDepth::Ansi16,
+        }
+    }
+}
+
+/// Number of colours the terminal can display, used to downsample truecolor values.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    #[default]
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    NoColor,
+}
+
+impl ColorDepth {
+    /// Best guess from `NO_COLOR`, `COLORTERM` and `TERM`.
+    pub fn from_env() -> Self {
+        Self::from_vars(|k| std::env::var(k).ok())
+    }
+
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        // https://no-color.org, set and not empty
+        if var("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            return Self::NoColor;
+        }
+        let colorterm = var("COLORTERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return Self::TrueColor;
+        }
+        let term = var("TERM").unwrap_or_default();
+        if term == "dumb" {
+            Self::NoColor
+        } else if term.contains("256color") {
+            Self::Ansi256
+        } else if term.is_empty() || term.contains("direct") {
+            Self::TrueColor
+        } else {
+            Self::Ansi16
+        }
+    }
+
+    pub fn downsample(self, c: Color) -> Color {
+        match (self, c) {
+            (Self::TrueColor, _) => c,
+            (Self::NoColor, _) => Color::Reset,
+            (Self::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_ansi256(r, g, b)),
+            (Self::Ansi256, _) => c,
+            (Self::Ansi16, Color::Reset) => c,
+            (Self::Ansi16, _) => match to_rgb(c) {
+                Some((r, g, b)) => nearest_ansi16(r, g, b),
+                None => c,
+            },
+        }
+    }
+
+    /// Maps every colour of `buf` to the palette. Without colours, cells drawn in
+    /// a dark colour are dimmed so the elapsed part of a shape stays visible.
+    pub fn apply(self, buf: &mut Buffer) {
+        if self == Self::TrueColor {
+            return;
+        }
+        let area = buf.area;
+        for pos in area.positions() {
+            let Some(cell) = buf.cell_mut(pos) else {
+                continue;
+            };
+            if self == Self::NoColor && to_rgb(cell.fg).is_some_and(|rgb| luminance(rgb) < 0.4) {
+                cell.modifier.insert(Modifier::DIM);
+            }
+            cell.fg = self.downsample(cell.fg);
+            cell.bg = self.downsample(cell.bg);
+        }
+    }
+}
+
+// relative luminance in 0.0..=1.0, no gamma correction
+fn luminance((r, g, b): (u8, u8, u8)) -> f64 {
+    (0.299 * f64::from(r) + 0.587 * f64::from(g) + 0.114 * f64::from(b)) / 255.0
+}
+
+// xterm default values for the 16 base colours
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// RGB value of a colour, `None` for `Reset` which depends on the terminal.
+pub fn to_rgb(c: Color) -> Option<(u8, u8, u8)> {
+    match c {
+        Color::Reset => None,
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        Color::Indexed(i) if i < 16 => Some(ANSI16[i as usize].1),
+        Color::Indexed(i) if i < 232 => {
+            let i = i - 16;
+            let (r, g, b) = (i / 36, (i / 6) % 6, i % 6);
+            Some((
+                CUBE_LEVELS[r as usize],
+                CUBE_LEVELS[g as usize],
+                CUBE_LEVELS[b as usize],
+            ))
+        }
+        Color::Indexed(i) => {
+            let v = 8 + (i - 232) * 10;
+            Some((v, v, v))
+        }
+        named => ANSI16
+            .iter()
+            .find(|(n, _)| *n == named)
+            .map(|(_, rgb)| *rgb),
+    }
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).unsigned_abs();
+    d(r1, r2).pow(2) + d(g1, g2).pow(2) + d(b1, b2).pow(2)
+}
+
+fn nearest_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |v: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| CUBE_LEVELS[i].abs_diff(v))
+            .unwrap_or(0) as u8
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = 16 + 36 * ri + 6 * gi + bi;
+    let cube_rgb = (
+        CUBE_LEVELS[ri as usize],
+        CUBE_LEVELS[gi as usize],
+        CUBE_LEVELS[bi as usize],
+    );
+
+    let avg = ((u16::from(r) + u16::from(g) + u16::from(b)) / 3) as u8;
+    let gray_i = (avg.saturating_sub(8) / 10).min(23);
+    let gray_v = 8 + gray_i * 10;
+
+    if distance((r, g, b), (gray_v, gray_v, gray_v)) < distance((r, g, b), cube_rgb) {
+        232 + gray_i
+    } else {
+        cube
+    }
+}
+
+fn nearest_ansi16(r: u8, g: u8, b: u8) -> Color {
+    ANSI16
+        .iter()
+        .min_by_key(|(_, rgb)| distance((r, g, b), *rgb))
+        .map_or(Color::Reset, |(c, _)| *c)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn when_parsing_should_override_base() {
+        let t = Theme::parse(
+            r##"
+            base = "solarized"
+            root = { bg = "#101830" }
+            title_bar.progress = { fg = "light-yellow", bold = true }
+            popup.selected = { bg = "yellow" }
+            [canvas]
+            fg = "236"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(Style::new().bg(Color::Rgb(16, 24, 48)), t.root);
+        assert_eq!(
+            Style::new()
+                .fg(Color::LightYellow)
+                .bg(BASE02)
+                .add_modifier(Modifier::BOLD),
+            t.title_bar.progress
+        );
+        assert_eq!(Color::Indexed(236), t.canvas.fg);
+        assert_eq!(SOLARIZED.canvas.bg, t.canvas.bg);
+        assert_eq!(SOLARIZED.player, t.player);
+        assert_eq!(Style::new().fg(BASE03).bg(Color::Yellow), t.popup.selected);
+        assert_eq!(SOLARIZED.popup.body, t.popup.body);
+        assert_eq!(DARK, Theme::parse("").unwrap());
+    }
+
+    #[test]
+    fn when_invalid_should_fail() {
+        for src in [
+            "base = \"neon\"",
+            "[canvas]\nfg = \"not a colour\"",
+            "[canvas]\nstroke = \"red\"",
+            "root = { bold = \"yes\" }",
+        ] {
+            assert!(Theme::parse(src).is_err(), "{src}");
+        }
+    }
+
+    #[test]
+    fn when_file_changes_should_reload() {
+        let path =
+            std::env::temp_dir().join(format!("crb-alarm-theme-{}.toml", std::process::id()));
+        std::fs::write(&path, "base = \"light\"").unwrap();
+        let mut w = ThemeWatcher::new(&path);
+        assert!(w.reload_if_changed().is_none());
+
+        // make sure the modification time moves even on coarse file systems
+        let later = SystemTime::now() + Duration::from_secs(5);
+        std::fs::write(&path, "base = \"high-contrast\"").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert_eq!(HIGH_CONTRAST, w.reload_if_changed().unwrap().unwrap());
+        assert!(w.reload_if_changed().is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    fn depth(vars: &[(&str, &str)]) -> ColorDepth {
+        ColorDepth::from_vars(|k| {
+            vars.iter()
+                .find(|(name, _)| *name == k)
+                .map(|(_, v)| v.to_string())
+        })
+    }
+
+    #[test]
+    fn when_detecting_depth_should_honour_no_color() {
+        assert_eq!(
+            ColorDepth::TrueColor,
+            depth(&[("COLORTERM", "truecolor"), ("TERM", "xterm-256color")])
+        );
+        assert_eq!(ColorDepth::Ansi256, depth(&[("TERM", "tmux-256color")]));
+        assert_eq!(ColorDepth::Ansi16, depth(&[("TERM", "linux")]));
+        assert_eq!(ColorDepth::NoColor, depth(&[("TERM", "dumb")]));
+        assert_eq!(
+            ColorDepth::NoColor,
+            depth(&[("NO_COLOR", "1"), ("COLORTERM", "truecolor")])
+        );
+        assert_eq!(
+            ColorDepth::Ansi256,
+            depth(&[("NO_COLOR", ""), ("TERM", "xterm-256color")])
+        );
+    }
+
+    #[test]
+    fn when_applying_depth_should_map_every_cell() {
+        let area = ratatui::layout::Rect::new(0, 0, 2, 1);
+        let mut buf = Buffer::empty(area);
+        buf[(0, 0)].set_fg(WHITE).set_bg(BLACK);
+        buf[(1, 0)].set_fg(DARK_GRAY);
+
+        let mut b = buf.clone();
+        ColorDepth::TrueColor.apply(&mut b);
+        assert_eq!(buf, b);
+
+        let mut b = buf.clone();
+        ColorDepth::Ansi256.apply(&mut b);
+        assert!(matches!(b[(0, 0)].fg, Color::Indexed(_)));
+        assert!(matches!(b[(0, 0)].bg, Color::Indexed(_)));
+
+        let mut b = buf.clone();
+        ColorDepth::Ansi16.apply(&mut b);
+        assert_eq!(Color::Black, b[(0, 0)].bg);
+
+        let mut b = buf;
+        ColorDepth::NoColor.apply(&mut b);
+        assert!(area
+            .positions()
+            .all(|p| b[p].fg == Color::Reset && b[p].bg == Color::Reset));
+        assert!(!b[(0, 0)].modifier.contains(Modifier::DIM));
+        assert!(b[(1, 0)].modifier.contains(Modifier::DIM));
+    }
+}
@@ -0,0 +1,124 @@
+use std::{sync::Arc, time::Duration};
+
+use cbr_alarm::app::App;
+use cbr_alarm::clock::ManualClock;
+use cbr_alarm::config::Preset;
+use cbr_alarm::event::AppEvent;
+use cbr_alarm::sound::Sound;
+use ratatui::{
+    backend::TestBackend,
+    crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    Terminal,
+};
+
+fn app(clock: &ManualClock, secs: u64) -> App {
+    let preset = Preset {
+        sound: Sound::Off,
+        ..Preset::new(Duration::from_secs(secs))
+    };
+    App::new(preset).with_clock(Arc::new(clock.clone()))
+}
+
+fn press(app: &mut App, c: char) {
+    app.update(AppEvent::Input(Event::Key(KeyEvent::from(KeyCode::Char(
+        c,
+    )))));
+}
+
+// ticks a second at a time, like the frames would
+fn run_for(app: &mut App, clock: &ManualClock, secs: u64) {
+    for _ in 0..secs {
+        clock.advance(Duration::from_secs(1));
+        app.tick();
+    }
+}
+
+// the title bar of the screen
+fn title(app: &mut App, terminal: &mut Terminal<TestBackend>) -> String {
+    terminal.draw(|frame| app.draw(frame)).unwrap();
+    let buffer = terminal.backend().buffer();
+    (0..buffer.area.width)
+        .map(|x| buffer[(x, 0)].symbol())
+        .collect()
+}
+
+#[test]
+fn when_the_clock_moves_should_count_down_complete_and_snooze() {
+    let clock = ManualClock::default();
+    let mut app = app(&clock, 90);
+    let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+    assert!(title(&mut app, &mut terminal).contains(" Time Left  1m 30s "));
+
+    run_for(&mut app, &clock, 60);
+    let t = title(&mut app, &mut terminal);
+    assert!(t.contains(" Time Left  30s "), "{t}");
+    assert!(t.contains("66.667%"), "{t}");
+
+    // nothing to snooze yet
+    press(&mut app, 'z');
+    assert!(title(&mut app, &mut terminal).contains(" Time Left  30s "));
+
+    run_for(&mut app, &clock, 30);
+    let t = title(&mut app, &mut terminal);
+    assert!(t.contains(" Time Left  0s "), "{t}");
+    assert!(t.contains("100.000%"), "{t}");
+
+    press(&mut app, 'z');
+    let t = title(&mut app, &mut terminal);
+    assert!(t.contains(" Time Left  5m 0s "), "{t}");
+    assert!(t.contains(" Total Duration  5m 0s "), "{t}");
+    assert!(t.contains(" SNOOZED "), "{t}");
+
+    run_for(&mut app, &clock, 300);
+    press(&mut app, 'z');
+    assert!(title(&mut app, &mut terminal).contains(" SNOOZED x2 "));
+
+    // a reset forgets the snoozes
+    press(&mut app, 'r');
+    let t = title(&mut app, &mut terminal);
+    assert!(t.contains(" Time Left  1m 30s "), "{t}");
+    assert!(!t.contains("SNOOZED"), "{t}");
+}
+
+#[test]
+fn when_paused_should_ignore_the_clock() {
+    let clock = ManualClock::default();
+    let mut app = app(&clock, 60);
+    let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+
+    press(&mut app, ' ');
+    run_for(&mut app, &clock, 600);
+    let t = title(&mut app, &mut terminal);
+    assert!(t.contains(" Time Left  1m 0s "), "{t}");
+    assert!(t.contains(" PAUSED "), "{t}");
+
+    press(&mut app, ' ');
+    // a jump of the clock counts once
+    clock.advance(Duration::from_secs(45));
+    app.tick();
+    assert!(title(&mut app, &mut terminal).contains(" Time Left  15s "));
+}
+
+#[test]
+fn when_picking_a_shape_in_the_palette_should_switch_to_it() {
+    let clock = ManualClock::default();
+    let mut app = app(&clock, 60);
+    let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+    let ctrl_p = KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL);
+    let enter = KeyEvent::from(KeyCode::Enter);
+
+    for query in ["change shape: life", "open logs"] {
+        app.update(AppEvent::Input(Event::Key(ctrl_p)));
+        query.chars().for_each(|c| press(&mut app, c));
+        app.update(AppEvent::Input(Event::Key(enter)));
+    }
+    terminal.draw(|frame| app.draw(frame)).unwrap();
+    let screen: String = terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|c| c.symbol())
+        .collect();
+    assert!(screen.contains("shape: life"), "{screen}");
+}